@@ -11,3 +11,51 @@ pub fn read_puzzle_input(day_count: usize) -> Vec<String> {
 
     data.lines().map(|s| s.to_string()).collect()
 }
+
+/// A small seedable pseudo random number generator (SplitMix64). It is not suitable for anything
+/// security related, but produces the same sequence for the same seed which keeps simulations
+/// and generated test data reproducible without pulling in an external crate.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `0..bound`. Panics if the bound is zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        assert!(bound > 0, "random bound must be greater than zero");
+
+        // Rejection sampling keeps the distribution uniform for bounds that don't evenly divide
+        // the generator's range.
+        let bound = bound as u64;
+        let zone = u64::MAX - (u64::MAX % bound);
+
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return (value % bound) as usize;
+            }
+        }
+    }
+
+    /// Shuffles the slice in place using a Fisher-Yates shuffle.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for idx in (1..items.len()).rev() {
+            let other = self.below(idx + 1);
+            items.swap(idx, other);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Tools for deciding which board is worth playing. Boards never interact with each other, so a
+//! board wins a game on exactly the draw it would have completed a pattern on if it were being
+//! played alone. Everything here is built on top of that isolated view of a board.
+
+use common::Rng;
+
+use crate::{Board, WinPattern};
+
+/// How a single board fared across a Monte Carlo run.
+#[derive(Debug, PartialEq)]
+pub struct BoardOdds {
+    pub board: usize,
+
+    /// The share of games this board won. Games where several boards win on the same draw
+    /// split the win evenly between them.
+    pub win_probability: f64,
+
+    /// The average draw index the board completed a pattern on, across the games where it
+    /// managed to complete one at all.
+    pub expected_win_turn: Option<f64>,
+}
+
+/// Returns the index of the draw that would complete one of the patterns on the board if it was
+/// the only board in play.
+pub fn isolated_win_turn(board: &Board, call_outs: &[usize], patterns: &[WinPattern]) -> Option<usize> {
+    let mut board = board.clone();
+
+    for (draw, number) in call_outs.iter().enumerate() {
+        board.mark_number(*number);
+
+        if board.winning_pattern(patterns).is_some() {
+            return Some(draw);
+        }
+    }
+
+    None
+}
+
+pub fn isolated_win_turns(boards: &[Board], call_outs: &[usize], patterns: &[WinPattern]) -> Vec<Option<usize>> {
+    boards.iter().map(|board| isolated_win_turn(board, call_outs, patterns)).collect()
+}
+
+/// Estimates each board's chance of winning by replaying the game with the draw order shuffled
+/// for each trial. The same seed will always produce the same estimates.
+pub fn estimate_odds(boards: &[Board], call_outs: &[usize], patterns: &[WinPattern], trials: usize, seed: u64) -> Vec<BoardOdds> {
+    let mut rng = Rng::new(seed);
+    let mut draws = call_outs.to_vec();
+
+    let mut wins = vec![0.0; boards.len()];
+    let mut turn_totals = vec![0; boards.len()];
+    let mut completions = vec![0; boards.len()];
+
+    for _ in 0..trials {
+        rng.shuffle(&mut draws);
+
+        let turns = isolated_win_turns(boards, &draws, patterns);
+        for (board_idx, turn) in turns.iter().enumerate() {
+            if let Some(turn) = turn {
+                turn_totals[board_idx] += turn;
+                completions[board_idx] += 1;
+            }
+        }
+
+        let winning_turn = match turns.iter().flatten().min() {
+            Some(turn) => *turn,
+            None => continue,
+        };
+
+        let winners: Vec<usize> = turns.iter()
+            .enumerate()
+            .filter(|(_, turn)| **turn == Some(winning_turn))
+            .map(|(board_idx, _)| board_idx)
+            .collect();
+
+        for board_idx in winners.iter() {
+            wins[*board_idx] += 1.0 / winners.len() as f64;
+        }
+    }
+
+    (0..boards.len())
+        .map(|board_idx| BoardOdds {
+            board: board_idx,
+            win_probability: if trials == 0 { 0.0 } else { wins[board_idx] / trials as f64 },
+            expected_win_turn: match completions[board_idx] {
+                0 => None,
+                count => Some(turn_totals[board_idx] as f64 / count as f64),
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::REFERENCE_INPUT;
+    use crate::{parse_boards, parse_random_header, DEFAULT_PATTERNS};
+
+    #[test]
+    fn test_isolated_win_turns() {
+        let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
+        let rng_nums = parse_random_header(&input[0]);
+        let board_list = parse_boards(&input[1..]).unwrap();
+
+        assert_eq!(isolated_win_turns(&board_list, &rng_nums, &DEFAULT_PATTERNS), vec![Some(13), Some(14), Some(11)]);
+        assert_eq!(isolated_win_turn(&board_list[0], &rng_nums[..13], &DEFAULT_PATTERNS), None);
+    }
+
+    #[test]
+    fn test_estimated_odds() {
+        let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
+        let rng_nums = parse_random_header(&input[0]);
+        let board_list = parse_boards(&input[1..]).unwrap();
+
+        let odds = estimate_odds(&board_list, &rng_nums, &DEFAULT_PATTERNS, 200, 7);
+        assert_eq!(odds, estimate_odds(&board_list, &rng_nums, &DEFAULT_PATTERNS, 200, 7));
+
+        // Every number on the boards gets drawn so each trial has a winner
+        let total: f64 = odds.iter().map(|o| o.win_probability).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!(odds.iter().all(|o| o.expected_win_turn.is_some()));
+
+        // Identical boards always tie and split every win between them
+        let twins = vec![board_list[0].clone(), board_list[0].clone()];
+        let odds = estimate_odds(&twins, &rng_nums, &DEFAULT_PATTERNS, 50, 1);
+        assert_eq!(odds[0].win_probability, 0.5);
+        assert_eq!(odds[0].expected_win_turn, odds[1].expected_win_turn);
+
+        assert!(estimate_odds(&board_list, &[], &DEFAULT_PATTERNS, 10, 1).iter().all(|o| o.win_probability == 0.0));
+    }
+}
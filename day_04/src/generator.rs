@@ -0,0 +1,285 @@
+//! Generates puzzle inputs in the same format as the real day four input: a comma separated
+//! header of draws followed by blank line separated 5x5 boards. Every number in the range is
+//! drawn exactly once and no board repeats a number, so generated puzzles always pass the
+//! strictest validation policy. Wins are decided with the traditional row and column rules.
+
+use std::ops::Range;
+
+use common::Rng;
+
+use crate::analysis::isolated_win_turn;
+use crate::{Board, DEFAULT_PATTERNS};
+
+const BOARD_SIZE: usize = 5;
+
+#[derive(Clone, Debug)]
+pub struct GeneratorConfig {
+    pub seed: u64,
+    pub numbers: Range<usize>,
+    pub board_count: usize,
+
+    /// Forces a board (by index) to be the sole first winner on a specific draw.
+    pub first_winner: Option<(usize, usize)>,
+
+    /// Requires that no two boards complete on the same draw.
+    pub distinct_win_draws: bool,
+
+    /// How many random boards are tried for each position before giving up on the constraints.
+    pub max_attempts: usize,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig {
+            seed: 0,
+            numbers: 0..100,
+            board_count: 100,
+            first_winner: None,
+            distinct_win_draws: false,
+            max_attempts: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GeneratorError {
+    RangeTooSmall { available: usize, required: usize },
+    InvalidWinner { board: usize, draw: usize },
+    Unsatisfiable { board: usize },
+}
+
+impl std::fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeneratorError::RangeTooSmall { available, required } => write!(
+                f,
+                "number range only holds {} numbers but at least {} are needed",
+                available, required
+            ),
+            GeneratorError::InvalidWinner { board, draw } => write!(
+                f,
+                "board {} can't be made to win first on draw {}",
+                board, draw
+            ),
+            GeneratorError::Unsatisfiable { board } => write!(
+                f,
+                "unable to find a board {} that satisfies the constraints",
+                board
+            ),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Puzzle {
+    pub call_outs: Vec<usize>,
+    pub boards: Vec<Board>,
+}
+
+impl std::fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let header: Vec<String> = self.call_outs.iter().map(|n| n.to_string()).collect();
+        writeln!(f, "{}", header.join(","))?;
+
+        let digits = self.boards.iter()
+            .flat_map(|b| b.numbers.iter())
+            .map(|(n, _)| n.to_string().len())
+            .max()
+            .unwrap_or(1);
+
+        for board in self.boards.iter() {
+            writeln!(f)?;
+
+            for row in board.numbers.chunks(board.width) {
+                let row: Vec<String> = row.iter().map(|(n, _)| format!("{:>width$}", n, width = digits)).collect();
+                writeln!(f, "{}", row.join(" "))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn generate(config: &GeneratorConfig) -> Result<Puzzle, GeneratorError> {
+    let mut rng = Rng::new(config.seed);
+
+    let available = config.numbers.len();
+    let required = BOARD_SIZE * BOARD_SIZE;
+    if available < required {
+        return Err(GeneratorError::RangeTooSmall { available, required });
+    }
+
+    let mut call_outs: Vec<usize> = config.numbers.clone().collect();
+    rng.shuffle(&mut call_outs);
+
+    let mut used_draws = vec![false; call_outs.len()];
+    let mut boards = Vec::with_capacity(config.board_count);
+
+    // Until the forced winner is placed every other board simply has to lose to it
+    let winning_draw = match config.first_winner {
+        Some((board, draw)) => {
+            if board >= config.board_count || !(BOARD_SIZE - 1..call_outs.len() - (required - BOARD_SIZE)).contains(&draw) {
+                return Err(GeneratorError::InvalidWinner { board, draw });
+            }
+
+            used_draws[draw] = true;
+            Some(draw)
+        }
+        None => None,
+    };
+
+    for board_idx in 0..config.board_count {
+        if let Some((winner, draw)) = config.first_winner {
+            if winner == board_idx {
+                boards.push(winning_board(&mut rng, &call_outs, draw));
+                continue;
+            }
+        }
+
+        let mut attempts = 0;
+        let board = loop {
+            if attempts == config.max_attempts {
+                return Err(GeneratorError::Unsatisfiable { board: board_idx });
+            }
+            attempts += 1;
+
+            let board = random_board(&mut rng, &call_outs);
+            let turn = match isolated_win_turn(&board, &call_outs, &DEFAULT_PATTERNS) {
+                Some(turn) => turn,
+                None => break board,
+            };
+
+            if winning_draw.is_some_and(|draw| turn <= draw) {
+                continue;
+            }
+
+            if config.distinct_win_draws {
+                if used_draws[turn] {
+                    continue;
+                }
+
+                used_draws[turn] = true;
+            }
+
+            break board;
+        };
+
+        boards.push(board);
+    }
+
+    Ok(Puzzle { call_outs, boards })
+}
+
+/// Picks distinct numbers for a board by partially shuffling the available numbers.
+fn random_board(rng: &mut Rng, call_outs: &[usize]) -> Board {
+    let mut pool = call_outs.to_vec();
+    let cell_count = BOARD_SIZE * BOARD_SIZE;
+
+    for idx in 0..cell_count {
+        let other = idx + rng.below(pool.len() - idx);
+        pool.swap(idx, other);
+    }
+
+    let numbers = pool[..cell_count].iter().map(|n| (*n, false)).collect();
+    Board::new(BOARD_SIZE, BOARD_SIZE, numbers).unwrap()
+}
+
+/// Builds a board that completes a random row or column exactly on the provided draw. The line
+/// is filled with the number drawn at that point along with numbers drawn before it, and every
+/// other cell uses numbers drawn afterwards so nothing else can complete sooner.
+fn winning_board(rng: &mut Rng, call_outs: &[usize], draw: usize) -> Board {
+    let mut earlier = call_outs[..draw].to_vec();
+    rng.shuffle(&mut earlier);
+
+    let mut line: Vec<usize> = earlier[..(BOARD_SIZE - 1)].to_vec();
+    line.push(call_outs[draw]);
+    rng.shuffle(&mut line);
+
+    let mut later = call_outs[(draw + 1)..].to_vec();
+    rng.shuffle(&mut later);
+
+    let line_idx = rng.below(BOARD_SIZE);
+    let is_row = rng.below(2) == 0;
+
+    let mut remaining = later.into_iter();
+    let mut line_cells = line.into_iter();
+    let numbers = (0..(BOARD_SIZE * BOARD_SIZE))
+        .map(|cell| {
+            let on_line = if is_row { cell / BOARD_SIZE == line_idx } else { cell % BOARD_SIZE == line_idx };
+
+            match on_line {
+                true => (line_cells.next().unwrap(), false),
+                false => (remaining.next().unwrap(), false),
+            }
+        })
+        .collect();
+
+    Board::new(BOARD_SIZE, BOARD_SIZE, numbers).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{check_game, parse_boards, parse_random_header, simulate, ValidationPolicy};
+
+    fn round_trip(puzzle: &Puzzle) -> Puzzle {
+        let input: Vec<String> = puzzle.to_string().lines().map(|e| e.to_string()).collect();
+
+        Puzzle {
+            call_outs: parse_random_header(&input[0]),
+            boards: parse_boards(&input[1..]).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let config = GeneratorConfig { seed: 12, board_count: 20, ..Default::default() };
+        let puzzle = generate(&config).unwrap();
+
+        assert_eq!(puzzle.call_outs.len(), 100);
+        assert_eq!(puzzle.boards.len(), 20);
+        assert_eq!(round_trip(&puzzle), puzzle);
+        assert_eq!(check_game(&puzzle.boards, &puzzle.call_outs, ValidationPolicy::Reject), Ok(vec![]));
+
+        assert_eq!(generate(&config).unwrap(), puzzle);
+
+        // Wider numbers still line up in their columns
+        let config = GeneratorConfig { numbers: 95..130, board_count: 3, ..Default::default() };
+        let puzzle = generate(&config).unwrap();
+        assert_eq!(round_trip(&puzzle), puzzle);
+    }
+
+    #[test]
+    fn test_forced_first_winner() {
+        let config = GeneratorConfig { seed: 3, board_count: 30, first_winner: Some((7, 12)), ..Default::default() };
+        let puzzle = generate(&config).unwrap();
+
+        let timeline = simulate(&puzzle.boards, &puzzle.call_outs, &DEFAULT_PATTERNS);
+        assert_eq!((timeline[0].board, timeline[0].draw), (7, 12));
+        assert!(timeline[1].draw > 12);
+
+        let config = GeneratorConfig { first_winner: Some((7, 2)), ..config };
+        assert_eq!(generate(&config), Err(GeneratorError::InvalidWinner { board: 7, draw: 2 }));
+    }
+
+    #[test]
+    fn test_distinct_win_draws() {
+        let config = GeneratorConfig { seed: 5, board_count: 25, distinct_win_draws: true, ..Default::default() };
+        let puzzle = generate(&config).unwrap();
+
+        let timeline = simulate(&puzzle.boards, &puzzle.call_outs, &DEFAULT_PATTERNS);
+        assert_eq!(timeline.len(), 25);
+        assert!(timeline.windows(2).all(|pair| pair[0].draw < pair[1].draw));
+    }
+
+    #[test]
+    fn test_generator_limits() {
+        let config = GeneratorConfig { numbers: 0..20, ..Default::default() };
+        assert_eq!(generate(&config), Err(GeneratorError::RangeTooSmall { available: 20, required: 25 }));
+
+        // There are only 25 draws so at most 21 distinct winning draws are possible
+        let config = GeneratorConfig { numbers: 0..25, board_count: 30, distinct_win_draws: true, max_attempts: 100, ..Default::default() };
+        assert!(matches!(generate(&config), Err(GeneratorError::Unsatisfiable { .. })));
+    }
+}
@@ -1,81 +1,737 @@
 #![allow(unused_variables)]
 
+mod analysis;
+mod generator;
+mod render;
+
+use std::collections::HashMap;
+
 use common::read_puzzle_input;
 
+/// A bingo card along with running hit counters for each row, column and diagonal. The counters
+/// are kept up to date as cells are marked so detecting a win never requires rescanning the
+/// card.
 #[derive(Clone, Debug, PartialEq)]
 struct Board {
-    numbers: [(usize, bool); 25],
+    numbers: Vec<(usize, bool)>,
+    width: usize,
+    height: usize,
+
+    row_hits: Vec<usize>,
+    col_hits: Vec<usize>,
+    diagonal_hits: usize,
+    anti_diagonal_hits: usize,
+    marked_count: usize,
+    unmarked_sum: usize,
 }
 
 impl Board {
+    fn new(width: usize, height: usize, numbers: Vec<(usize, bool)>) -> Result<Self, BoardError> {
+        if width == 0 || height == 0 {
+            return Err(BoardError::Empty);
+        }
+
+        if numbers.len() != width * height {
+            return Err(BoardError::WrongCellCount { expected: width * height, found: numbers.len() });
+        }
+
+        let mut board = Board {
+            numbers: numbers.iter().map(|(num, _)| (*num, false)).collect(),
+            width,
+            height,
+
+            row_hits: vec![0; height],
+            col_hits: vec![0; width],
+            diagonal_hits: 0,
+            anti_diagonal_hits: 0,
+            marked_count: 0,
+            unmarked_sum: numbers.iter().map(|(num, _)| num).sum(),
+        };
+
+        for (idx, _) in numbers.iter().enumerate().filter(|(_, (_, marked))| *marked) {
+            board.mark_cell(idx);
+        }
+
+        Ok(board)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    #[cfg(test)]
     fn is_solved(&self) -> bool {
-        // Check rows
-        if self.numbers.chunks(5).any(|row| row.iter().all(|(_, mark)| *mark)) {
-            return true;
+        self.row_hits.contains(&self.width) || self.col_hits.contains(&self.height)
+    }
+
+    /// Returns the first of the provided patterns that is fully marked on this board.
+    fn winning_pattern<'a>(&self, patterns: &'a [WinPattern]) -> Option<&'a WinPattern> {
+        patterns.iter().find(|pattern| pattern.is_complete(self))
+    }
+
+    /// Marks a single cell updating the hit counters, returns false if the cell was already
+    /// marked.
+    fn mark_cell(&mut self, idx: usize) -> bool {
+        let (number, marked) = &mut self.numbers[idx];
+        if *marked {
+            return false;
         }
 
-        // Check columns
-        for column in 0..5 {
-            if self.numbers.chunks(5).all(|row| row[column].1) {
-                return true;
+        *marked = true;
+        self.unmarked_sum -= *number;
+        self.marked_count += 1;
+
+        let (row, col) = (idx / self.width, idx % self.width);
+        self.row_hits[row] += 1;
+        self.col_hits[col] += 1;
+
+        if self.width == self.height {
+            if row == col {
+                self.diagonal_hits += 1;
+            }
+
+            if row + col == self.width - 1 {
+                self.anti_diagonal_hits += 1;
             }
         }
 
-        false
+        true
     }
 
     fn mark_number(&mut self, number: usize) {
-        for (_num, marked) in self.numbers.iter_mut().filter(|(n, _)| n == &number) {
-            *marked = true;
+        let matching: Vec<usize> = self.numbers.iter()
+            .enumerate()
+            .filter(|(_, (n, _))| *n == number)
+            .map(|(idx, _)| idx)
+            .collect();
+
+        for idx in matching {
+            self.mark_cell(idx);
         }
     }
 
     fn unmarked_score(&self) -> usize {
-        self.numbers.iter().filter(|(_, marked)| !marked).map(|(num, _)| num).sum()
+        self.unmarked_sum
+    }
+}
+
+impl TryFrom<&[String]> for Board {
+    type Error = BoardError;
+
+    fn try_from(input: &[String]) -> Result<Board, BoardError> {
+        let board_lines: Vec<&str> = input.iter().map(|l| l.trim()).filter(|l| !l.is_empty()).collect();
+
+        let mut rows: Vec<Vec<usize>> = vec![];
+        for line in board_lines.iter() {
+            let row = line.split_whitespace()
+                .map(|e| e.parse::<usize>().map_err(|_| BoardError::InvalidNumber(e.to_string())))
+                .collect::<Result<Vec<usize>, BoardError>>()?;
+
+            rows.push(row);
+        }
+
+        let width = match rows.first() {
+            Some(row) => row.len(),
+            None => return Err(BoardError::Empty),
+        };
+
+        for (row_idx, row) in rows.iter().enumerate() {
+            if row.len() != width {
+                return Err(BoardError::RaggedRow { row: row_idx, expected: width, found: row.len() });
+            }
+        }
+
+        let height = rows.len();
+        let numbers: Vec<(usize, bool)> = rows.into_iter().flatten().map(|n| (n, false)).collect();
+
+        Board::new(width, height, numbers)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum BoardError {
+    Empty,
+    InvalidNumber(String),
+    MismatchedSize { board: usize, expected: (usize, usize), found: (usize, usize) },
+    RaggedRow { row: usize, expected: usize, found: usize },
+    WrongCellCount { expected: usize, found: usize },
+}
+
+impl std::fmt::Display for BoardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoardError::Empty => write!(f, "board contains no numbers"),
+            BoardError::InvalidNumber(value) => write!(f, "'{}' is not a valid board number", value),
+            BoardError::MismatchedSize { board, expected, found } => write!(
+                f,
+                "board {} is {}x{} but the game is played with {}x{} boards",
+                board, found.0, found.1, expected.0, expected.1
+            ),
+            BoardError::RaggedRow { row, expected, found } => write!(
+                f,
+                "board row {} has {} numbers, expected {}",
+                row, found, expected
+            ),
+            BoardError::WrongCellCount { expected, found } => write!(
+                f,
+                "board has {} numbers, expected {}",
+                found, expected
+            ),
+        }
     }
 }
 
-impl From<&[String]> for Board {
-    fn from(input: &[String]) -> Board {
-        let board_lines: Vec<&str> = input[1..].iter().map(|l| l.trim()).collect();
+/// The traditional bingo rules, any complete row or column wins.
+const DEFAULT_PATTERNS: [WinPattern; 2] = [WinPattern::Row, WinPattern::Column];
 
-        let raw_numbers: Vec<usize> = board_lines.iter()
-                .map(|line| {
-                    line.split(' ')
-                        .filter(|e| !e.is_empty())
-                        .map(|e| e.parse::<usize>().unwrap())
-                        .collect::<Vec<usize>>()
-                })
-                .flatten()
-                .collect();
+#[derive(Clone, Debug, PartialEq)]
+enum WinPattern {
+    Row,
+    Column,
+    Diagonal,
+    AntiDiagonal,
+    FourCorners,
+    Blackout,
+    Custom(Mask),
+}
+
+impl WinPattern {
+    /// Each group of cell indices that would satisfy this pattern on a board of the provided
+    /// size. The diagonals only exist on square boards and custom masks only apply to boards of
+    /// the same size as the mask.
+    fn cell_groups(&self, width: usize, height: usize) -> Vec<Vec<usize>> {
+        match self {
+            WinPattern::Row => (0..height).map(|row| (0..width).map(|col| row * width + col).collect()).collect(),
+            WinPattern::Column => (0..width).map(|col| (0..height).map(|row| row * width + col).collect()).collect(),
+            WinPattern::Diagonal if width == height => vec![(0..width).map(|i| i * width + i).collect()],
+            WinPattern::AntiDiagonal if width == height => vec![(0..width).map(|i| i * width + (width - 1 - i)).collect()],
+            WinPattern::Diagonal | WinPattern::AntiDiagonal => vec![],
+            WinPattern::FourCorners => {
+                let mut corners = vec![0, width - 1, (height - 1) * width, height * width - 1];
+                corners.sort_unstable();
+                corners.dedup();
+                vec![corners]
+            }
+            WinPattern::Blackout => vec![(0..(width * height)).collect()],
+            WinPattern::Custom(mask) if mask.width == width && mask.height == height => {
+                vec![mask.cells.iter().enumerate().filter(|(_, set)| **set).map(|(idx, _)| idx).collect()]
+            }
+            WinPattern::Custom(_) => vec![],
+        }
+    }
+
+    /// Checks whether this pattern is complete anywhere on the board using the board's hit
+    /// counters.
+    fn is_complete(&self, board: &Board) -> bool {
+        let (width, height) = board.dimensions();
+
+        match self {
+            WinPattern::Row => board.row_hits.contains(&width),
+            WinPattern::Column => board.col_hits.contains(&height),
+            WinPattern::Diagonal => width == height && board.diagonal_hits == width,
+            WinPattern::AntiDiagonal => width == height && board.anti_diagonal_hits == width,
+            WinPattern::FourCorners => {
+                let corners = [0, width - 1, (height - 1) * width, height * width - 1];
+                corners.iter().all(|idx| board.numbers[*idx].1)
+            }
+            WinPattern::Blackout => board.marked_count == board.numbers.len(),
+            WinPattern::Custom(mask) => {
+                mask.width == width
+                    && mask.height == height
+                    && mask.cells.iter().zip(board.numbers.iter()).all(|(required, (_, marked))| !required || *marked)
+            }
+        }
+    }
+
+    /// Checks whether this pattern is complete on the board through the provided cell using the
+    /// board's hit counters. Custom masks don't have counters of their own so they check each of
+    /// their cells, but only when the cell is part of the mask.
+    fn is_completed_by(&self, board: &Board, cell: usize) -> bool {
+        let (width, height) = board.dimensions();
+        let (row, col) = (cell / width, cell % width);
+
+        match self {
+            WinPattern::Row => board.row_hits[row] == width,
+            WinPattern::Column => board.col_hits[col] == height,
+            WinPattern::Diagonal => width == height && row == col && board.diagonal_hits == width,
+            WinPattern::AntiDiagonal => width == height && row + col == width - 1 && board.anti_diagonal_hits == width,
+            WinPattern::FourCorners => {
+                let corners = [0, width - 1, (height - 1) * width, height * width - 1];
+                corners.contains(&cell) && corners.iter().all(|idx| board.numbers[*idx].1)
+            }
+            WinPattern::Blackout => board.marked_count == board.numbers.len(),
+            WinPattern::Custom(mask) => {
+                mask.width == width
+                    && mask.height == height
+                    && mask.cells[cell]
+                    && mask.cells.iter().zip(board.numbers.iter()).all(|(required, (_, marked))| !required || *marked)
+            }
+        }
+    }
+
+    /// Finds a fully marked instance of this pattern on the board returning the cells that make
+    /// it up.
+    fn matched_cells(&self, board: &Board) -> Option<Vec<usize>> {
+        self.cell_groups(board.width, board.height)
+            .into_iter()
+            .find(|cells| cells.iter().all(|idx| board.numbers[*idx].1))
+    }
+}
+
+impl std::fmt::Display for WinPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WinPattern::Row => write!(f, "row"),
+            WinPattern::Column => write!(f, "column"),
+            WinPattern::Diagonal => write!(f, "diagonal"),
+            WinPattern::AntiDiagonal => write!(f, "anti-diagonal"),
+            WinPattern::FourCorners => write!(f, "corners"),
+            WinPattern::Blackout => write!(f, "blackout"),
+            WinPattern::Custom(mask) => write!(f, "{}", mask.name),
+        }
+    }
+}
 
-        let board_numbers: Vec<(usize, bool)> = raw_numbers.into_iter().map(|n| (n, false)).collect();
-        let mut numbers: [(usize, bool); 25] =  Default::default();
-        numbers.copy_from_slice(&board_numbers[..25]);
+/// Parses a comma separated list of the built-in pattern names. The name "diagonals" is
+/// accepted as a shorthand for both diagonal directions.
+fn parse_pattern_names(list: &str) -> Result<Vec<WinPattern>, PatternError> {
+    let mut patterns = vec![];
 
-        Board { numbers }
+    for name in list.split(',').map(|n| n.trim()).filter(|n| !n.is_empty()) {
+        match name {
+            "row" | "rows" => patterns.push(WinPattern::Row),
+            "column" | "columns" => patterns.push(WinPattern::Column),
+            "diagonal" => patterns.push(WinPattern::Diagonal),
+            "anti-diagonal" => patterns.push(WinPattern::AntiDiagonal),
+            "diagonals" => {
+                patterns.push(WinPattern::Diagonal);
+                patterns.push(WinPattern::AntiDiagonal);
+            }
+            "corners" => patterns.push(WinPattern::FourCorners),
+            "blackout" => patterns.push(WinPattern::Blackout),
+            _ => return Err(PatternError::UnknownPattern(name.to_string())),
+        }
     }
+
+    Ok(patterns)
+}
+
+/// A user supplied set of cells that all need to be marked for a board to win.
+#[derive(Clone, Debug, PartialEq)]
+struct Mask {
+    name: String,
+    cells: Vec<bool>,
+    width: usize,
+    height: usize,
+}
+
+/// Parses custom masks from a small text format. Each mask is a grid of `X` (required) and `.`
+/// (free) characters with masks separated by blank lines. A mask may be given a name with a
+/// leading `name: <name>` line and lines starting with `#` are ignored.
+///
+/// ```text
+/// name: plus
+/// ..X..
+/// ..X..
+/// XXXXX
+/// ..X..
+/// ..X..
+/// ```
+fn parse_masks(input: &str) -> Result<Vec<WinPattern>, PatternError> {
+    let mut masks = vec![];
+    let mut name: Option<String> = None;
+    let mut rows: Vec<Vec<bool>> = vec![];
+
+    // A trailing empty line flushes the final mask the same way a separator does
+    let lines = input.lines().chain(std::iter::once(""));
+
+    for (line_idx, line) in lines.enumerate() {
+        let line = line.trim();
+
+        if line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(mask_name) = line.strip_prefix("name:") {
+            name = Some(mask_name.trim().to_string());
+            continue;
+        }
+
+        if line.is_empty() {
+            if rows.is_empty() {
+                continue;
+            }
+
+            let name = name.take().unwrap_or_else(|| format!("custom {}", masks.len() + 1));
+            let (width, height) = (rows[0].len(), rows.len());
+            let cells: Vec<bool> = rows.drain(..).flatten().collect();
+
+            if !cells.contains(&true) {
+                return Err(PatternError::EmptyMask(name));
+            }
+
+            masks.push(WinPattern::Custom(Mask { name, cells, width, height }));
+            continue;
+        }
+
+        let row = line.chars()
+            .map(|c| match c {
+                'X' | 'x' => Ok(true),
+                '.' => Ok(false),
+                _ => Err(PatternError::InvalidMaskCharacter { line: line_idx + 1, character: c }),
+            })
+            .collect::<Result<Vec<bool>, PatternError>>()?;
+
+        if let Some(first) = rows.first() {
+            if first.len() != row.len() {
+                return Err(PatternError::RaggedMask { line: line_idx + 1 });
+            }
+        }
+
+        rows.push(row);
+    }
+
+    Ok(masks)
+}
+
+#[derive(Debug, PartialEq)]
+enum PatternError {
+    EmptyMask(String),
+    InvalidMaskCharacter { line: usize, character: char },
+    RaggedMask { line: usize },
+    UnknownPattern(String),
+}
+
+impl std::fmt::Display for PatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatternError::EmptyMask(name) => write!(f, "mask '{}' does not require any cells", name),
+            PatternError::InvalidMaskCharacter { line, character } => write!(
+                f,
+                "unexpected character '{}' in mask on line {}, expected 'X' or '.'",
+                character, line
+            ),
+            PatternError::RaggedMask { line } => write!(f, "mask row on line {} differs in width from the rows above it", line),
+            PatternError::UnknownPattern(name) => write!(f, "unknown win pattern '{}'", name),
+        }
+    }
+}
+
+/// A single board completing one of the game's win patterns. The draw is the index into the
+/// call out list of the number that completed the pattern.
+#[derive(Clone, Debug, PartialEq)]
+struct WinEvent {
+    board: usize,
+    draw: usize,
+    number: usize,
+    pattern: WinPattern,
+    score: usize,
 }
 
 fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(config) = options.generate.as_ref() {
+        match generator::generate(config) {
+            Ok(puzzle) => print!("{}", puzzle),
+            Err(err) => {
+                eprintln!("unable to generate puzzle: {}", err);
+                std::process::exit(1);
+            }
+        }
+
+        return;
+    }
+
     let input = read_puzzle_input(4);
     let rng_nums = parse_random_header(&input[0]);
 
-    let mut board_list = vec![];
-    for board in input[1..].chunks(6) {
-        board_list.push(parse_board(board));
+    let board_list = match parse_boards(&input[1..]) {
+        Ok(boards) => boards,
+        Err(err) => {
+            eprintln!("invalid board input: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    match check_game(&board_list, &rng_nums, options.policy) {
+        Ok(issues) => {
+            for issue in issues.iter() {
+                eprintln!("warning: {}", issue);
+            }
+        }
+        Err(issues) => {
+            for issue in issues.iter() {
+                eprintln!("error: {}", issue);
+            }
+            std::process::exit(1);
+        }
+    }
+
+    if options.step {
+        print_steps(&board_list, &rng_nums, &options);
+    }
+
+    let timeline = simulate(&board_list, &rng_nums, &options.patterns);
+
+    let first = timeline.first();
+    println!("first part result: {:?}", first.map(|w| w.score));
+    if let Some(win) = first {
+        println!("  board {} won with the {} pattern on draw {}", win.board, win.pattern, win.draw);
+    }
+
+    let last = timeline.last();
+    println!("second part result: {:?}", last.map(|w| w.score));
+    if let Some(win) = last {
+        println!("  board {} won with the {} pattern on draw {}", win.board, win.pattern, win.draw);
+    }
+
+    if options.render {
+        let winners: Vec<&WinEvent> = first.into_iter().chain(last).collect();
+        print_winners(&board_list, &rng_nums, &winners, &options);
+    }
+
+    if options.analyze {
+        print_analysis(&board_list, &rng_nums, &options);
+    }
+}
+
+/// Shows the state of every board after each draw until all of them have won.
+fn print_steps(boards: &[Board], call_outs: &[usize], options: &Options) {
+    let mut game = Game::new(boards.to_vec(), &options.patterns);
+    let mut winning_patterns: HashMap<usize, WinPattern> = HashMap::new();
+
+    for (draw, number) in call_outs.iter().enumerate() {
+        for event in game.call(draw, *number) {
+            winning_patterns.insert(event.board, event.pattern);
+        }
+
+        let views: Vec<render::BoardView> = game.boards.iter()
+            .enumerate()
+            .map(|(board_idx, board)| {
+                let highlight = winning_patterns.get(&board_idx)
+                    .and_then(|pattern| pattern.matched_cells(board))
+                    .unwrap_or_default();
+
+                let title = match winning_patterns.get(&board_idx) {
+                    Some(pattern) => format!("board {} ({})", board_idx, pattern),
+                    None => format!("board {}", board_idx),
+                };
+
+                render::BoardView { title, board, highlight }
+            })
+            .collect();
+
+        println!("draw {}: {}", draw, number);
+        println!("{}", render::render_boards(&views, &options.render_options));
+
+        if winning_patterns.len() == boards.len() {
+            break;
+        }
+    }
+}
+
+/// Shows the winning boards as they were at the moment they won.
+fn print_winners(boards: &[Board], call_outs: &[usize], winners: &[&WinEvent], options: &Options) {
+    let states: Vec<(&WinEvent, Board)> = winners.iter()
+        .map(|win| {
+            let mut board = boards[win.board].clone();
+            for number in call_outs[..=win.draw].iter() {
+                board.mark_number(*number);
+            }
+
+            (*win, board)
+        })
+        .collect();
+
+    let views: Vec<render::BoardView> = states.iter()
+        .map(|(win, board)| render::BoardView {
+            title: format!("board {} on draw {}", win.board, win.draw),
+            board,
+            highlight: win.pattern.matched_cells(board).unwrap_or_default(),
+        })
+        .collect();
+
+    print!("{}", render::render_boards(&views, &options.render_options));
+}
+
+fn print_analysis(boards: &[Board], call_outs: &[usize], options: &Options) {
+    let mut isolated: Vec<(usize, usize)> = analysis::isolated_win_turns(boards, call_outs, &options.patterns)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(board_idx, turn)| turn.map(|t| (board_idx, t)))
+        .collect();
+    isolated.sort_by_key(|(board_idx, turn)| (*turn, *board_idx));
+
+    println!("earliest isolated wins:");
+    for (board_idx, turn) in isolated.iter().take(5) {
+        println!("  board {} on draw {}", board_idx, turn);
+    }
+
+    let mut odds = analysis::estimate_odds(boards, call_outs, &options.patterns, options.trials, options.seed);
+    odds.sort_by(|a, b| b.win_probability.total_cmp(&a.win_probability));
+
+    println!("estimated odds over {} shuffled games (seed {}):", options.trials, options.seed);
+    for board_odds in odds.iter().take(5) {
+        let expected_turn = match board_odds.expected_win_turn {
+            Some(turn) => format!("{:.1}", turn),
+            None => "never".to_string(),
+        };
+
+        println!(
+            "  board {} wins {:.2}% of games, expected to complete on draw {}",
+            board_odds.board, board_odds.win_probability * 100.0, expected_turn
+        );
+    }
+}
+
+struct Options {
+    patterns: Vec<WinPattern>,
+    policy: ValidationPolicy,
+
+    analyze: bool,
+    trials: usize,
+    seed: u64,
+
+    generate: Option<generator::GeneratorConfig>,
+
+    render: bool,
+    step: bool,
+    render_options: render::RenderOptions,
+}
+
+/// Parses the command line options:
+///
+/// * `--patterns <names>` a comma separated list of built-in win patterns
+/// * `--masks <file>` a file of custom win masks
+/// * `--validation <reject|warn|accept>` how to treat questionable input, defaults to warn
+/// * `--analyze` reports each board's isolated win turn and estimated odds of winning
+/// * `--trials <count>` the number of shuffled games used for the odds, defaults to 1000
+/// * `--seed <seed>` seeds the shuffled games and generated puzzles, defaults to 0
+/// * `--generate` prints a generated puzzle instead of solving the real one
+/// * `--boards <count>` the number of boards to generate, defaults to 100
+/// * `--numbers <start>..<end>` the range of numbers to generate from, defaults to 0..100
+/// * `--winner <board>@<draw>` forces a generated board to be the first to win on a draw
+/// * `--distinct-wins` prevents generated boards from winning on the same draw
+/// * `--render` shows the first and last winning boards
+/// * `--step` shows the state of every board after each draw
+/// * `--ansi` highlights marked numbers with ANSI bold instead of brackets
+/// * `--width <columns>` the terminal width to fit boards into, defaults to `$COLUMNS` or 80
+///
+/// Without any patterns the traditional row and column rules are used.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut patterns = vec![];
+    let mut policy = ValidationPolicy::Warn;
+    let mut analyze = false;
+    let mut trials = 1000;
+    let mut seed = 0;
+    let mut generate = false;
+    let mut generator_config = generator::GeneratorConfig::default();
+    let mut render = false;
+    let mut step = false;
+    let mut render_options = render::RenderOptions::default();
+
+    if let Some(columns) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        render_options.terminal_width = columns;
+    }
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--patterns" => {
+                let list = args.next().unwrap_or_default();
+                patterns.extend(parse_pattern_names(&list).map_err(|err| format!("invalid win pattern: {}", err))?);
+            }
+            "--masks" => {
+                let path = args.next().unwrap_or_default();
+                let contents = std::fs::read_to_string(&path)
+                    .map_err(|err| format!("unable to read mask file {}: {}", path, err))?;
+                patterns.extend(parse_masks(&contents).map_err(|err| format!("invalid win pattern: {}", err))?);
+            }
+            "--validation" => {
+                policy = args.next().unwrap_or_default().parse()?;
+            }
+            "--analyze" => analyze = true,
+            "--trials" => {
+                let value = args.next().unwrap_or_default();
+                trials = value.parse().map_err(|_| format!("invalid trial count '{}'", value))?;
+            }
+            "--seed" => {
+                let value = args.next().unwrap_or_default();
+                seed = value.parse().map_err(|_| format!("invalid seed '{}'", value))?;
+            }
+            "--generate" => generate = true,
+            "--boards" => {
+                let value = args.next().unwrap_or_default();
+                generator_config.board_count = value.parse().map_err(|_| format!("invalid board count '{}'", value))?;
+            }
+            "--numbers" => {
+                let value = args.next().unwrap_or_default();
+                let (start, end) = value.split_once("..").ok_or(format!("invalid number range '{}'", value))?;
+                let start = start.parse().map_err(|_| format!("invalid number range '{}'", value))?;
+                let end = end.parse().map_err(|_| format!("invalid number range '{}'", value))?;
+                generator_config.numbers = start..end;
+            }
+            "--winner" => {
+                let value = args.next().unwrap_or_default();
+                let (board, draw) = value.split_once('@').ok_or(format!("invalid winner '{}', expected <board>@<draw>", value))?;
+                let board = board.parse().map_err(|_| format!("invalid winner '{}'", value))?;
+                let draw = draw.parse().map_err(|_| format!("invalid winner '{}'", value))?;
+                generator_config.first_winner = Some((board, draw));
+            }
+            "--distinct-wins" => generator_config.distinct_win_draws = true,
+            "--render" => render = true,
+            "--step" => step = true,
+            "--ansi" => render_options.ansi = true,
+            "--width" => {
+                let value = args.next().unwrap_or_default();
+                render_options.terminal_width = value.parse().map_err(|_| format!("invalid terminal width '{}'", value))?;
+            }
+            _ => (),
+        }
+    }
+
+    if patterns.is_empty() {
+        patterns.extend(DEFAULT_PATTERNS);
     }
 
-    let mut first_game = board_list.clone();
-    let result = play_boards(&mut first_game, &rng_nums);
-    println!("first part result: {:?}", result);
+    generator_config.seed = seed;
+    let generate = if generate { Some(generator_config) } else { None };
 
-    let result = last_board(board_list, &rng_nums);
-    println!("second part result: {:?}", result);
+    Ok(Options { patterns, policy, analyze, trials, seed, generate, render, step, render_options })
 }
 
-fn parse_board(lines: &[String]) -> Board {
-    Board::from(lines)
+fn parse_board(lines: &[String]) -> Result<Board, BoardError> {
+    Board::try_from(lines)
+}
+
+/// Parses every blank line separated board in the provided lines. All boards in a single game
+/// need to share the same dimensions, the first board parsed sets the expected size.
+fn parse_boards(lines: &[String]) -> Result<Vec<Board>, BoardError> {
+    let mut boards: Vec<Board> = vec![];
+
+    for block in lines.split(|l| l.trim().is_empty()).filter(|b| !b.is_empty()) {
+        let board = parse_board(block)?;
+
+        if let Some(first) = boards.first() {
+            if first.dimensions() != board.dimensions() {
+                return Err(BoardError::MismatchedSize {
+                    board: boards.len(),
+                    expected: first.dimensions(),
+                    found: board.dimensions(),
+                });
+            }
+        }
+
+        boards.push(board);
+    }
+
+    Ok(boards)
 }
 
 fn parse_random_header(line: &str) -> Vec<usize> {
@@ -85,42 +741,206 @@ fn parse_random_header(line: &str) -> Vec<usize> {
         .collect()
 }
 
-fn last_board(mut boards: Vec<Board>, call_outs: &Vec<usize>) -> Option<usize> {
-    for number in call_outs.iter() {
+/// How questionable but playable input should be treated. Accepting it plays the game the same
+/// way it always has: every copy of a repeated number on a board is marked together, and a
+/// repeated draw has no further effect.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ValidationPolicy {
+    Reject,
+    Warn,
+    Accept,
+}
+
+impl std::str::FromStr for ValidationPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "reject" => Ok(ValidationPolicy::Reject),
+            "warn" => Ok(ValidationPolicy::Warn),
+            "accept" => Ok(ValidationPolicy::Accept),
+            _ => Err(format!("unknown validation policy '{}', expected reject, warn or accept", value)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ValidationIssue {
+    DuplicateNumber { board: usize, number: usize, cells: Vec<usize> },
+    NeverDrawn { board: usize, number: usize },
+    RepeatedDraw { number: usize, draws: Vec<usize> },
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationIssue::DuplicateNumber { board, number, cells } => write!(
+                f,
+                "board {} contains {} more than once (cells {:?})",
+                board, number, cells
+            ),
+            ValidationIssue::NeverDrawn { board, number } => write!(f, "board {} contains {} which is never drawn", board, number),
+            ValidationIssue::RepeatedDraw { number, draws } => write!(f, "{} is drawn more than once (draws {:?})", number, draws),
+        }
+    }
+}
+
+/// Collects every problem with the game's input. Duplicate numbers and numbers that are never
+/// drawn are reported per board in cell order, followed by repeated draws in the order they were
+/// first drawn.
+fn validate_game(boards: &[Board], call_outs: &[usize]) -> Vec<ValidationIssue> {
+    let mut issues = vec![];
+
+    let mut draw_positions: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (draw, number) in call_outs.iter().enumerate() {
+        draw_positions.entry(*number).or_default().push(draw);
+    }
+
+    for (board_idx, board) in boards.iter().enumerate() {
+        let mut cell_positions: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (cell_idx, (number, _)) in board.numbers.iter().enumerate() {
+            cell_positions.entry(*number).or_default().push(cell_idx);
+        }
+
+        for (cell_idx, (number, _)) in board.numbers.iter().enumerate() {
+            let cells = &cell_positions[number];
+
+            // Only report each number once, on its first occurrence
+            if cells[0] != cell_idx {
+                continue;
+            }
 
-        for board in boards.iter_mut() {
-            board.mark_number(*number);
+            if cells.len() > 1 {
+                issues.push(ValidationIssue::DuplicateNumber { board: board_idx, number: *number, cells: cells.clone() });
+            }
+
+            if !draw_positions.contains_key(number) {
+                issues.push(ValidationIssue::NeverDrawn { board: board_idx, number: *number });
+            }
         }
+    }
+
+    for (draw, number) in call_outs.iter().enumerate() {
+        let draws = &draw_positions[number];
 
-        if boards.len() == 1 && boards[0].is_solved() {
-            return Some(*number * boards[0].unmarked_score());
-        } else {
-            boards = boards.into_iter().filter(|b| !b.is_solved()).collect();
+        if draws.len() > 1 && draws[0] == draw {
+            issues.push(ValidationIssue::RepeatedDraw { number: *number, draws: draws.clone() });
         }
     }
 
-    None
+    issues
 }
 
-fn play_boards(boards: &mut Vec<Board>, call_outs: &Vec<usize>) -> Option<usize> {
-    for number in call_outs.iter() {
-        for board in boards.iter_mut() {
-            board.mark_number(*number);
+/// Applies the validation policy to the game's input. Warnings are returned for the caller to
+/// report while a rejected game returns every issue found as an error.
+fn check_game(boards: &[Board], call_outs: &[usize], policy: ValidationPolicy) -> Result<Vec<ValidationIssue>, Vec<ValidationIssue>> {
+    if policy == ValidationPolicy::Accept {
+        return Ok(vec![]);
+    }
 
-            if board.is_solved() {
-                return Some(*number * board.unmarked_score());
+    let issues = validate_game(boards, call_outs);
+
+    if policy == ValidationPolicy::Reject && !issues.is_empty() {
+        return Err(issues);
+    }
+
+    Ok(issues)
+}
+
+/// A game in progress. Every number on every board is indexed up front so calling a number only
+/// touches the cells holding it rather than scanning each board.
+struct Game {
+    boards: Vec<Board>,
+    index: HashMap<usize, Vec<(usize, usize)>>,
+    patterns: Vec<WinPattern>,
+    won: Vec<bool>,
+}
+
+impl Game {
+    fn new(boards: Vec<Board>, patterns: &[WinPattern]) -> Self {
+        let mut index: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+
+        for (board_idx, board) in boards.iter().enumerate() {
+            for (cell_idx, (number, _)) in board.numbers.iter().enumerate() {
+                index.entry(*number).or_default().push((board_idx, cell_idx));
             }
         }
+
+        let won = vec![false; boards.len()];
+
+        Game { boards, index, patterns: patterns.to_vec(), won }
     }
 
-    None
+    /// Marks the number on every board that hasn't won yet, returning the boards that won as a
+    /// result ordered by their position in the game.
+    fn call(&mut self, draw: usize, number: usize) -> Vec<WinEvent> {
+        let mut events = vec![];
+
+        let cells = match self.index.get(&number) {
+            Some(cells) => cells,
+            None => return events,
+        };
+
+        // The index is built in board order so all cells for a board are adjacent. A board can
+        // hold the same number more than once, every copy is marked before checking for a win
+        // to keep the score consistent.
+        for board_cells in cells.chunk_by(|a, b| a.0 == b.0) {
+            let board_idx = board_cells[0].0;
+            if self.won[board_idx] {
+                continue;
+            }
+
+            let board = &mut self.boards[board_idx];
+            for (_, cell_idx) in board_cells.iter() {
+                board.mark_cell(*cell_idx);
+            }
+
+            let pattern = self.patterns.iter().find(|pattern| {
+                board_cells.iter().any(|(_, cell_idx)| pattern.is_completed_by(board, *cell_idx))
+            });
+
+            if let Some(pattern) = pattern {
+                self.won[board_idx] = true;
+
+                events.push(WinEvent {
+                    board: board_idx,
+                    draw,
+                    number,
+                    pattern: pattern.clone(),
+                    score: number * board.unmarked_score(),
+                });
+            }
+        }
+
+        events
+    }
+}
+
+/// Plays the called numbers against every board recording each board the moment it wins. Boards
+/// stop playing once they've won, so every board appears in the timeline at most once. Boards
+/// winning on the same draw are ordered by their position in the game.
+fn simulate(boards: &[Board], call_outs: &[usize], patterns: &[WinPattern]) -> Vec<WinEvent> {
+    let mut game = Game::new(boards.to_vec(), patterns);
+    let mut timeline = vec![];
+
+    for (draw, number) in call_outs.iter().enumerate() {
+        timeline.extend(game.call(draw, *number));
+
+        if timeline.len() == boards.len() {
+            break;
+        }
+    }
+
+    timeline
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const REFERENCE_INPUT: &str =
+    use common::Rng;
+
+    pub(crate) const REFERENCE_INPUT: &str =
         "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1\n\
                                    \n\
                                    22 13 17 11  0\n\
@@ -157,12 +977,13 @@ mod tests {
 
         let rng_nums = parse_random_header(&input[0]);
 
-        let mut board_list = vec![];
-        for board in input[1..].chunks(6) {
-            board_list.push(parse_board(board));
-        }
+        let board_list = parse_boards(&input[1..]).unwrap();
 
-        assert_eq!(play_boards(&mut board_list, &rng_nums), Some(4512));
+        let timeline = simulate(&board_list, &rng_nums, &DEFAULT_PATTERNS);
+        let win = timeline.first().unwrap();
+        assert_eq!(win.board, 2);
+        assert_eq!(win.pattern, WinPattern::Row);
+        assert_eq!(win.score, 4512);
     }
 
     #[test]
@@ -171,51 +992,43 @@ mod tests {
 
         let rng_nums = parse_random_header(&input[0]);
 
-        let mut board_list = vec![];
-        for board in input[1..].chunks(6) {
-            board_list.push(parse_board(board));
-        }
+        let board_list = parse_boards(&input[1..]).unwrap();
 
-        assert_eq!(last_board(board_list, &rng_nums), Some(1924));
+        let timeline = simulate(&board_list, &rng_nums, &DEFAULT_PATTERNS);
+        let win = timeline.last().unwrap();
+        assert_eq!(win.board, 1);
+        assert_eq!(win.pattern, WinPattern::Column);
+        assert_eq!(win.score, 1924);
     }
 
     #[test]
     fn test_board_parser() {
         let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
 
-        let mut board_list = vec![];
-        for board in input[1..].chunks(6) {
-            board_list.push(parse_board(board));
-        }
+        let board_list = parse_boards(&input[1..]).unwrap();
 
         let expected_boards = vec![
-            Board {
-                numbers: [
-                    (22, false), (13, false), (17, false), (11, false), (0, false),
-                    (8, false),  (2, false),  (23, false), (4, false),  (24, false),
-                    (21, false), (9, false),  (14, false), (16, false), (7, false),
-                    (6, false),  (10, false), (3, false),  (18, false), (5, false),
-                    (1, false),  (12, false), (20, false), (15, false), (19, false),
-                ]
-            },
-            Board {
-                numbers: [
-                    (3, false),  (15, false), (0, false),  (2, false),  (22, false),
-                    (9, false),  (18, false), (13, false), (17, false), (5, false),
-                    (19, false), (8, false),  (7, false),  (25, false), (23, false),
-                    (20, false), (11, false), (10, false), (24, false), (4, false),
-                    (14, false), (21, false), (16, false), (12, false), (6, false),
-                ]
-            },
-            Board {
-                numbers: [
-                    (14, false), (21, false), (17, false), (24, false), (4, false),
-                    (10, false), (16, false), (15, false), (9, false),  (19, false),
-                    (18, false), (8, false),  (23, false), (26, false), (20, false),
-                    (22, false), (11, false), (13, false), (6, false),  (5, false),
-                    (2, false),  (0, false),  (12, false), (3, false),  (7, false),
-                ]
-            },
+            Board::new(5, 5, vec![
+                (22, false), (13, false), (17, false), (11, false), (0, false),
+                (8, false),  (2, false),  (23, false), (4, false),  (24, false),
+                (21, false), (9, false),  (14, false), (16, false), (7, false),
+                (6, false),  (10, false), (3, false),  (18, false), (5, false),
+                (1, false),  (12, false), (20, false), (15, false), (19, false),
+            ]).unwrap(),
+            Board::new(5, 5, vec![
+                (3, false),  (15, false), (0, false),  (2, false),  (22, false),
+                (9, false),  (18, false), (13, false), (17, false), (5, false),
+                (19, false), (8, false),  (7, false),  (25, false), (23, false),
+                (20, false), (11, false), (10, false), (24, false), (4, false),
+                (14, false), (21, false), (16, false), (12, false), (6, false),
+            ]).unwrap(),
+            Board::new(5, 5, vec![
+                (14, false), (21, false), (17, false), (24, false), (4, false),
+                (10, false), (16, false), (15, false), (9, false),  (19, false),
+                (18, false), (8, false),  (23, false), (26, false), (20, false),
+                (22, false), (11, false), (13, false), (6, false),  (5, false),
+                (2, false),  (0, false),  (12, false), (3, false),  (7, false),
+            ]).unwrap(),
         ];
 
         assert_eq!(board_list, expected_boards);
@@ -223,15 +1036,13 @@ mod tests {
 
     #[test]
     fn test_board_marking() {
-        let mut board = Board {
-            numbers: [
-                (0, false), (1, false), (2, false), (3, false), (4, false),
-                (5, false), (6, false), (7, false), (8, false),  (9, false),
-                (10, false), (11, false),  (12, false), (13, false), (14, false),
-                (15, false), (6, false), (13, false), (1, false),  (2, false),
-                (24, false),  (0, false),  (12, false), (3, false),  (9, false),
-            ]
-        };
+        let mut board = Board::new(5, 5, vec![
+            (0, false), (1, false), (2, false), (3, false), (4, false),
+            (5, false), (6, false), (7, false), (8, false),  (9, false),
+            (10, false), (11, false),  (12, false), (13, false), (14, false),
+            (15, false), (6, false), (13, false), (1, false),  (2, false),
+            (24, false),  (0, false),  (12, false), (3, false),  (9, false),
+        ]).unwrap();
 
         board.mark_number(2);
         assert!(board.numbers[2].1);
@@ -240,78 +1051,315 @@ mod tests {
         board.mark_number(24);
         assert!(board.numbers[20].1);
 
-        let expected_board = Board {
-            numbers: [
-                (0, false), (1, false), (2, true), (3, false), (4, false),
-                (5, false), (6, false), (7, false), (8, false),  (9, false),
-                (10, false), (11, false),  (12, false), (13, false), (14, false),
-                (15, false), (6, false), (13, false), (1, false),  (2, true),
-                (24, true),  (0, false),  (12, false), (3, false),  (9, false),
-            ]
-        };
+        let expected_board = Board::new(5, 5, vec![
+            (0, false), (1, false), (2, true), (3, false), (4, false),
+            (5, false), (6, false), (7, false), (8, false),  (9, false),
+            (10, false), (11, false),  (12, false), (13, false), (14, false),
+            (15, false), (6, false), (13, false), (1, false),  (2, true),
+            (24, true),  (0, false),  (12, false), (3, false),  (9, false),
+        ]).unwrap();
 
         assert_eq!(board, expected_board);
     }
 
     #[test]
     fn test_board_scoring() {
-        let board = Board {
-            numbers: [
-                (14, true), (21, true), (17, true), (24, true), (4, true),
-                (10, false), (16, false), (15, false), (9, true),  (19, false),
-                (18, false), (8, false),  (23, true), (26, false), (20, false),
-                (22, false), (11, true), (13, false), (6, false),  (5, true),
-                (2, true),  (0, true),  (12, false), (3, false),  (7, true),
-            ]
-        };
+        let board = Board::new(5, 5, vec![
+            (14, true), (21, true), (17, true), (24, true), (4, true),
+            (10, false), (16, false), (15, false), (9, true),  (19, false),
+            (18, false), (8, false),  (23, true), (26, false), (20, false),
+            (22, false), (11, true), (13, false), (6, false),  (5, true),
+            (2, true),  (0, true),  (12, false), (3, false),  (7, true),
+        ]).unwrap();
 
         assert_eq!(board.unmarked_score(), 188);
     }
 
     #[test]
     fn test_solution_verification() {
-        let board = Board {
-            numbers: [
-                (14, false), (21, false), (17, false), (24, false), (4, false),
-                (10, false), (16, false), (15, false), (9, false),  (19, false),
-                (18, false), (8, false),  (23, false), (26, false), (20, false),
-                (22, false), (11, false), (13, false), (6, false),  (5, false),
-                (2, false),  (0, false),  (12, false), (3, false),  (7, false),
-            ]
-        };
+        let board = Board::new(5, 5, vec![
+            (14, false), (21, false), (17, false), (24, false), (4, false),
+            (10, false), (16, false), (15, false), (9, false),  (19, false),
+            (18, false), (8, false),  (23, false), (26, false), (20, false),
+            (22, false), (11, false), (13, false), (6, false),  (5, false),
+            (2, false),  (0, false),  (12, false), (3, false),  (7, false),
+        ]).unwrap();
         assert!(!board.is_solved());
 
-        let board = Board {
-            numbers: [
-                (14, false), (21, false), (17, false), (24, false), (4, false),
-                (10, true), (16, true), (15, true), (9, true),  (19, true),
-                (18, false), (8, false),  (23, false), (26, false), (20, false),
-                (22, false), (11, false), (13, false), (6, false),  (5, false),
-                (2, false),  (0, false),  (12, false), (3, false),  (7, false),
-            ]
-        };
+        let board = Board::new(5, 5, vec![
+            (14, false), (21, false), (17, false), (24, false), (4, false),
+            (10, true), (16, true), (15, true), (9, true),  (19, true),
+            (18, false), (8, false),  (23, false), (26, false), (20, false),
+            (22, false), (11, false), (13, false), (6, false),  (5, false),
+            (2, false),  (0, false),  (12, false), (3, false),  (7, false),
+        ]).unwrap();
         assert!(board.is_solved());
 
-        let board = Board {
-            numbers: [
-                (14, false), (21, false), (17, false), (24, false), (4, true),
-                (10, false), (16, false), (15, false), (9, false),  (19, true),
-                (18, false), (8, false),  (23, false), (26, false), (20, true),
-                (22, false), (11, false), (13, false), (6, false),  (5, true),
-                (2, false),  (0, false),  (12, false), (3, false),  (7, true),
-            ]
-        };
+        let board = Board::new(5, 5, vec![
+            (14, false), (21, false), (17, false), (24, false), (4, true),
+            (10, false), (16, false), (15, false), (9, false),  (19, true),
+            (18, false), (8, false),  (23, false), (26, false), (20, true),
+            (22, false), (11, false), (13, false), (6, false),  (5, true),
+            (2, false),  (0, false),  (12, false), (3, false),  (7, true),
+        ]).unwrap();
         assert!(board.is_solved());
 
-        let board = Board {
-            numbers: [
-                (14, true), (21, false), (17, false), (24, false), (4, false),
-                (10, false), (16, true), (15, false), (9, false),  (19, false),
-                (18, false), (8, false),  (23, true), (26, false), (20, false),
-                (22, false), (11, false), (13, false), (6, true),  (5, false),
-                (2, false),  (0, false),  (12, false), (3, false),  (7, true),
-            ]
-        };
+        let board = Board::new(5, 5, vec![
+            (14, true), (21, false), (17, false), (24, false), (4, false),
+            (10, false), (16, true), (15, false), (9, false),  (19, false),
+            (18, false), (8, false),  (23, true), (26, false), (20, false),
+            (22, false), (11, false), (13, false), (6, true),  (5, false),
+            (2, false),  (0, false),  (12, false), (3, false),  (7, true),
+        ]).unwrap();
         assert!(!board.is_solved());
     }
+
+    #[test]
+    fn test_board_dimensions() {
+        let input: Vec<String> = "1 2 3\n4 5 6\n\n7 8 9\n10 11 12".lines().map(|e| e.to_string()).collect();
+        let boards = parse_boards(&input).unwrap();
+
+        assert_eq!(boards.len(), 2);
+        assert_eq!(boards[0].dimensions(), (3, 2));
+        assert_eq!(boards[1].numbers[4], (11, false));
+
+        let mut board = boards[0].clone();
+        board.mark_number(2);
+        assert!(!board.is_solved());
+        board.mark_number(5);
+        assert!(board.is_solved());
+
+        let mut board = boards[1].clone();
+        board.mark_number(10);
+        board.mark_number(11);
+        assert!(!board.is_solved());
+        board.mark_number(12);
+        assert!(board.is_solved());
+    }
+
+    #[test]
+    fn test_board_size_validation() {
+        let input: Vec<String> = "1 2 3\n4 5\n".lines().map(|e| e.to_string()).collect();
+        assert_eq!(
+            parse_boards(&input),
+            Err(BoardError::RaggedRow { row: 1, expected: 3, found: 2 })
+        );
+
+        let input: Vec<String> = "1 2\n3 4\n\n1 2 3\n4 5 6\n7 8 9".lines().map(|e| e.to_string()).collect();
+        let err = parse_boards(&input).unwrap_err();
+        assert_eq!(err, BoardError::MismatchedSize { board: 1, expected: (2, 2), found: (3, 3) });
+        assert_eq!(err.to_string(), "board 1 is 3x3 but the game is played with 2x2 boards");
+
+        let input: Vec<String> = "1 2\n3 x".lines().map(|e| e.to_string()).collect();
+        assert_eq!(parse_boards(&input), Err(BoardError::InvalidNumber("x".to_string())));
+
+        assert_eq!(Board::new(2, 2, vec![(1, false)]), Err(BoardError::WrongCellCount { expected: 4, found: 1 }));
+    }
+
+    #[test]
+    fn test_win_patterns() {
+        let mut board = Board::new(3, 3, (1..=9).map(|n| (n, false)).collect()).unwrap();
+        let patterns = [WinPattern::Diagonal, WinPattern::AntiDiagonal, WinPattern::FourCorners, WinPattern::Blackout];
+
+        for n in [1, 5] {
+            board.mark_number(n);
+        }
+        assert_eq!(board.winning_pattern(&patterns), None);
+
+        board.mark_number(9);
+        assert_eq!(board.winning_pattern(&patterns), Some(&WinPattern::Diagonal));
+        assert_eq!(WinPattern::Diagonal.matched_cells(&board), Some(vec![0, 4, 8]));
+        assert!(!board.is_solved());
+
+        let mut board = Board::new(3, 3, (1..=9).map(|n| (n, false)).collect()).unwrap();
+        for n in [3, 5, 7] {
+            board.mark_number(n);
+        }
+        assert_eq!(board.winning_pattern(&patterns), Some(&WinPattern::AntiDiagonal));
+
+        let mut board = Board::new(3, 3, (1..=9).map(|n| (n, false)).collect()).unwrap();
+        for n in [1, 3, 7, 9] {
+            board.mark_number(n);
+        }
+        assert_eq!(board.winning_pattern(&patterns), Some(&WinPattern::FourCorners));
+
+        let mut board = Board::new(2, 2, (1..=4).map(|n| (n, false)).collect()).unwrap();
+        for n in [1, 2, 3] {
+            board.mark_number(n);
+        }
+        assert_eq!(board.winning_pattern(&[WinPattern::Blackout]), None);
+        board.mark_number(4);
+        assert_eq!(board.winning_pattern(&[WinPattern::Blackout]), Some(&WinPattern::Blackout));
+
+        // Diagonals don't exist on rectangular boards
+        assert!(WinPattern::Diagonal.cell_groups(3, 2).is_empty());
+    }
+
+    #[test]
+    fn test_mask_parsing() {
+        let masks = parse_masks("# an x through the middle\nname: cross\nX.X\n.X.\nX.X\n\n.X.\n.X.\n").unwrap();
+        assert_eq!(masks.len(), 2);
+
+        let cross = match &masks[0] {
+            WinPattern::Custom(mask) => mask,
+            _ => panic!("expected a custom mask"),
+        };
+        assert_eq!(cross.name, "cross");
+        assert_eq!((cross.width, cross.height), (3, 3));
+        assert_eq!(masks[0].cell_groups(3, 3), vec![vec![0, 2, 4, 6, 8]]);
+        assert!(masks[0].cell_groups(5, 5).is_empty());
+        assert_eq!(masks[1].to_string(), "custom 2");
+
+        assert_eq!(parse_masks("X.\nX"), Err(PatternError::RaggedMask { line: 2 }));
+        assert_eq!(parse_masks("X.o"), Err(PatternError::InvalidMaskCharacter { line: 1, character: 'o' }));
+        assert_eq!(parse_masks("name: nothing\n..\n.."), Err(PatternError::EmptyMask("nothing".to_string())));
+
+        assert_eq!(
+            parse_pattern_names("row, diagonals,blackout"),
+            Ok(vec![WinPattern::Row, WinPattern::Diagonal, WinPattern::AntiDiagonal, WinPattern::Blackout])
+        );
+        assert_eq!(parse_pattern_names("zigzag"), Err(PatternError::UnknownPattern("zigzag".to_string())));
+    }
+
+    #[test]
+    fn test_combined_patterns() {
+        let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
+        let rng_nums = parse_random_header(&input[0]);
+        let board_list = parse_boards(&input[1..]).unwrap();
+
+        // The corners of the third board are all called before its winning row
+        let patterns = [WinPattern::Row, WinPattern::Column, WinPattern::FourCorners];
+        let timeline = simulate(&board_list, &rng_nums, &patterns);
+        let win = &timeline[0];
+
+        assert_eq!(win.board, 2);
+        assert_eq!(win.pattern, WinPattern::FourCorners);
+        assert_eq!(win.score, 233 * 14);
+    }
+
+    #[test]
+    fn test_simulation_timeline() {
+        let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
+        let rng_nums = parse_random_header(&input[0]);
+        let board_list = parse_boards(&input[1..]).unwrap();
+
+        let timeline = simulate(&board_list, &rng_nums, &DEFAULT_PATTERNS);
+
+        let order: Vec<(usize, usize, usize)> = timeline.iter().map(|w| (w.board, w.draw, w.number)).collect();
+        assert_eq!(order, vec![(2, 11, 24), (0, 13, 16), (1, 14, 13)]);
+
+        assert_eq!(timeline[0].score, 4512);
+        assert_eq!(timeline[2].score, 1924);
+
+        // A game that runs out of numbers only reports the boards that managed to win
+        let timeline = simulate(&board_list, &rng_nums[..12], &DEFAULT_PATTERNS);
+        assert_eq!(timeline.len(), 1);
+        assert!(simulate(&board_list, &[], &DEFAULT_PATTERNS).is_empty());
+    }
+
+    fn random_boards(rng: &mut Rng, count: usize, size: usize, number_range: usize) -> Vec<Board> {
+        (0..count)
+            .map(|_| {
+                let numbers = (0..(size * size)).map(|_| (rng.below(number_range), false)).collect();
+                Board::new(size, size, numbers).unwrap()
+            })
+            .collect()
+    }
+
+    /// The original approach of marking and rescanning every board on every draw, kept to check
+    /// the indexed game against.
+    fn scanning_simulation(boards: &[Board], call_outs: &[usize], patterns: &[WinPattern]) -> Vec<WinEvent> {
+        let mut remaining: Vec<(usize, Board)> = boards.iter().cloned().enumerate().collect();
+        let mut timeline = vec![];
+
+        for (draw, number) in call_outs.iter().enumerate() {
+            remaining.retain_mut(|(board_idx, board)| {
+                board.mark_number(*number);
+
+                match board.winning_pattern(patterns) {
+                    Some(pattern) => {
+                        let score = *number * board.numbers.iter().filter(|(_, m)| !m).map(|(n, _)| n).sum::<usize>();
+                        timeline.push(WinEvent { board: *board_idx, draw, number: *number, pattern: pattern.clone(), score });
+                        false
+                    }
+                    None => true,
+                }
+            });
+        }
+
+        timeline
+    }
+
+    #[test]
+    fn test_indexed_game_matches_scanning() {
+        let mut rng = Rng::new(2021);
+        let masks = parse_masks("X...X\n.....\n..X..\n.....\nX...X").unwrap();
+
+        let mut patterns = vec![WinPattern::Row, WinPattern::Column, WinPattern::Diagonal, WinPattern::AntiDiagonal];
+        patterns.extend(masks);
+
+        for _ in 0..20 {
+            // The small number range guarantees boards with repeated numbers
+            let boards = random_boards(&mut rng, 50, 5, 60);
+            let call_outs: Vec<usize> = (0..80).map(|_| rng.below(60)).collect();
+
+            assert_eq!(simulate(&boards, &call_outs, &patterns), scanning_simulation(&boards, &call_outs, &patterns));
+            assert_eq!(simulate(&boards, &call_outs, &[WinPattern::Blackout]), scanning_simulation(&boards, &call_outs, &[WinPattern::Blackout]));
+        }
+    }
+
+    /// Run with `cargo test --release -p day_04 -- --ignored --nocapture` to see the timings.
+    #[test]
+    #[ignore]
+    fn bench_large_game() {
+        let mut rng = Rng::new(4);
+        let boards = random_boards(&mut rng, 10_000, 5, 1_000);
+        let mut call_outs: Vec<usize> = (0..1_000).collect();
+        rng.shuffle(&mut call_outs);
+
+        let start = std::time::Instant::now();
+        let timeline = simulate(&boards, &call_outs, &DEFAULT_PATTERNS);
+        let indexed = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let reference = scanning_simulation(&boards, &call_outs, &DEFAULT_PATTERNS);
+        let scanning = start.elapsed();
+
+        println!("10,000 boards / 1,000 draws: indexed {:?}, scanning {:?}", indexed, scanning);
+        assert_eq!(timeline, reference);
+    }
+
+    #[test]
+    fn test_game_validation() {
+        let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
+        let rng_nums = parse_random_header(&input[0]);
+        let board_list = parse_boards(&input[1..]).unwrap();
+
+        assert!(validate_game(&board_list, &rng_nums).is_empty());
+
+        let boards = vec![Board::new(2, 2, vec![(1, false), (2, false), (1, false), (9, false)]).unwrap()];
+        let call_outs = vec![1, 2, 3, 2, 4, 2];
+
+        let expected = vec![
+            ValidationIssue::DuplicateNumber { board: 0, number: 1, cells: vec![0, 2] },
+            ValidationIssue::NeverDrawn { board: 0, number: 9 },
+            ValidationIssue::RepeatedDraw { number: 2, draws: vec![1, 3, 5] },
+        ];
+        assert_eq!(validate_game(&boards, &call_outs), expected);
+        assert_eq!(expected[0].to_string(), "board 0 contains 1 more than once (cells [0, 2])");
+
+        assert_eq!(check_game(&boards, &call_outs, ValidationPolicy::Reject), Err(expected));
+        assert_eq!(check_game(&boards, &call_outs, ValidationPolicy::Warn).unwrap().len(), 3);
+        assert_eq!(check_game(&boards, &call_outs, ValidationPolicy::Accept), Ok(vec![]));
+        assert_eq!(check_game(&board_list, &rng_nums, ValidationPolicy::Reject), Ok(vec![]));
+
+        // Accepted duplicates keep the original behaviour of marking every copy at once
+        let timeline = simulate(&boards, &call_outs, &DEFAULT_PATTERNS);
+        assert_eq!(timeline[0].draw, 0);
+        assert_eq!(timeline[0].pattern, WinPattern::Column);
+        assert_eq!(timeline[0].score, 11);
+
+        assert_eq!("warn".parse::<ValidationPolicy>(), Ok(ValidationPolicy::Warn));
+        assert!("maybe".parse::<ValidationPolicy>().is_err());
+    }
 }
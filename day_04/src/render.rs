@@ -0,0 +1,202 @@
+//! Renders boards for the terminal. Marked numbers are wrapped in brackets, or shown in bold when
+//! ANSI output is enabled, and the cells of a winning pattern are emphasized further. Several
+//! boards are laid out side by side, wrapping onto new bands to fit the terminal width.
+
+use crate::Board;
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_BOLD: &str = "\x1b[1m";
+const ANSI_WINNING: &str = "\x1b[1;7m";
+
+/// The space left between boards rendered next to each other.
+const BOARD_GAP: usize = 3;
+
+#[derive(Clone, Copy, Debug)]
+pub struct RenderOptions {
+    pub ansi: bool,
+    pub terminal_width: usize,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions { ansi: false, terminal_width: 80 }
+    }
+}
+
+/// A board ready for display along with a title and any cells that should be emphasized.
+pub struct BoardView<'a> {
+    pub title: String,
+    pub board: &'a Board,
+    pub highlight: Vec<usize>,
+}
+
+impl std::fmt::Display for Board {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in board_lines(self, &[], false, number_width(self)) {
+            writeln!(f, "{}", line.trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn number_width(board: &Board) -> usize {
+    board.numbers.iter().map(|(n, _)| n.to_string().len()).max().unwrap_or(1)
+}
+
+/// Each cell is padded by one column on either side, which holds the brackets around marked
+/// numbers when ANSI output is disabled.
+fn cell_width(digits: usize) -> usize {
+    digits + 2
+}
+
+fn rendered_width(board: &Board, digits: usize) -> usize {
+    board.width * cell_width(digits) + (board.width - 1)
+}
+
+fn render_cell(number: usize, marked: bool, winning: bool, ansi: bool, digits: usize) -> String {
+    match (ansi, winning, marked) {
+        (true, true, _) => format!("{} {:>w$} {}", ANSI_WINNING, number, ANSI_RESET, w = digits),
+        (true, false, true) => format!("{} {:>w$} {}", ANSI_BOLD, number, ANSI_RESET, w = digits),
+        (false, true, _) => format!("*{:>w$}*", number, w = digits),
+        (false, false, true) => format!("[{:>w$}]", number, w = digits),
+        (_, false, false) => format!(" {:>w$} ", number, w = digits),
+    }
+}
+
+fn board_lines(board: &Board, highlight: &[usize], ansi: bool, digits: usize) -> Vec<String> {
+    board.numbers
+        .chunks(board.width)
+        .enumerate()
+        .map(|(row_idx, row)| {
+            let cells: Vec<String> = row.iter()
+                .enumerate()
+                .map(|(col_idx, (number, marked))| {
+                    let winning = highlight.contains(&(row_idx * board.width + col_idx));
+                    render_cell(*number, *marked, winning, ansi, digits)
+                })
+                .collect();
+
+            cells.join(" ")
+        })
+        .collect()
+}
+
+/// Lays the boards out side by side, starting a new band of boards whenever the next one would
+/// run past the terminal width. A board wider than the terminal is still given a band of its
+/// own.
+pub fn render_boards(views: &[BoardView], options: &RenderOptions) -> String {
+    let digits = views.iter().map(|v| number_width(v.board)).max().unwrap_or(1);
+
+    let mut bands: Vec<Vec<&BoardView>> = vec![];
+    let mut band_width = 0;
+
+    for view in views.iter() {
+        let width = rendered_width(view.board, digits).max(view.title.len());
+
+        match bands.last_mut() {
+            Some(band) if band_width + BOARD_GAP + width <= options.terminal_width => {
+                band.push(view);
+                band_width += BOARD_GAP + width;
+            }
+            _ => {
+                bands.push(vec![view]);
+                band_width = width;
+            }
+        }
+    }
+
+    let mut output = String::new();
+
+    for (band_idx, band) in bands.iter().enumerate() {
+        if band_idx > 0 {
+            output.push('\n');
+        }
+
+        let widths: Vec<usize> = band.iter().map(|v| rendered_width(v.board, digits).max(v.title.len())).collect();
+        let rendered: Vec<Vec<String>> = band.iter().map(|v| board_lines(v.board, &v.highlight, options.ansi, digits)).collect();
+        let height = band.iter().map(|v| v.board.height).max().unwrap_or(0);
+
+        let titles: Vec<String> = band.iter().zip(widths.iter()).map(|(v, w)| format!("{:<w$}", v.title, w = w)).collect();
+        output.push_str(titles.join(&" ".repeat(BOARD_GAP)).trim_end());
+        output.push('\n');
+
+        for row_idx in 0..height {
+            let mut line = String::new();
+
+            for (view_idx, lines) in rendered.iter().enumerate() {
+                if view_idx > 0 {
+                    line.push_str(&" ".repeat(BOARD_GAP));
+                }
+
+                // Board lines already share a single visible width, only missing rows of
+                // shorter boards need padding.
+                let board_width = rendered_width(band[view_idx].board, digits);
+                match lines.get(row_idx) {
+                    Some(board_line) => line.push_str(board_line),
+                    None => line.push_str(&" ".repeat(board_width)),
+                }
+
+                line.push_str(&" ".repeat(widths[view_idx] - board_width));
+            }
+
+            output.push_str(line.trim_end());
+            output.push('\n');
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::WinPattern;
+
+    fn sample_board() -> Board {
+        Board::new(3, 2, vec![(1, true), (22, false), (3, true), (4, false), (5, true), (16, false)]).unwrap()
+    }
+
+    #[test]
+    fn test_board_display() {
+        assert_eq!(sample_board().to_string(), "[ 1]  22  [ 3]\n  4  [ 5]  16\n");
+    }
+
+    #[test]
+    fn test_winning_line_highlight() {
+        let mut board = sample_board();
+        board.mark_number(22);
+
+        let highlight = WinPattern::Row.matched_cells(&board).unwrap();
+        let views = vec![BoardView { title: "board 0".to_string(), board: &board, highlight }];
+
+        let plain = render_boards(&views, &RenderOptions::default());
+        assert_eq!(plain, "board 0\n* 1* *22* * 3*\n  4  [ 5]  16\n");
+
+        let ansi = render_boards(&views, &RenderOptions { ansi: true, terminal_width: 80 });
+        assert!(ansi.contains("\x1b[1;7m 22 \x1b[0m"));
+        assert!(ansi.contains("\x1b[1m  5 \x1b[0m"));
+    }
+
+    #[test]
+    fn test_side_by_side_layout() {
+        let board = sample_board();
+        let views: Vec<BoardView> = (0..3)
+            .map(|idx| BoardView { title: format!("board {}", idx), board: &board, highlight: vec![] })
+            .collect();
+
+        // Each board is 14 columns wide so only two fit next to each other
+        let output = render_boards(&views, &RenderOptions { ansi: false, terminal_width: 34 });
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[0], "board 0          board 1");
+        assert_eq!(lines[1], "[ 1]  22  [ 3]   [ 1]  22  [ 3]");
+        assert_eq!(lines[3], "");
+        assert_eq!(lines[4], "board 2");
+
+        let output = render_boards(&views, &RenderOptions { ansi: false, terminal_width: 80 });
+        assert_eq!(output.lines().count(), 3);
+    }
+}
@@ -0,0 +1,260 @@
+//! Writes the board out as an image. The raster formats are the binary Netpbm formats, PGM for
+//! grayscale and PPM for colour, which need nothing more than a short header in front of the
+//! pixel data. SVG output only includes the cells with at least one line passing through them
+//! and can draw the original lines over the top.
+//!
+//! Every format scales its heatmap to the highest overlap count on the board, so the result stays
+//! readable no matter how many lines pile up on a single cell. Grayscale runs from black for an
+//! empty cell up to white, while the colour scale starts at a single line and leaves empty cells
+//! black.
+
+use std::fmt::Write;
+
+use crate::{Board, Line, Point};
+
+/// The most pixels a raster export will hold. Sparse boards can span far more cells than could
+/// ever be written out as pixels, SVG output only covers the marked cells and has no limit.
+const PIXEL_LIMIT: u128 = 100_000_000;
+
+/// Raised when a raster export would need more than `PIXEL_LIMIT` pixels.
+#[derive(Debug, PartialEq)]
+pub struct TooLarge {
+    pub width: u128,
+    pub height: u128,
+}
+
+impl std::fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "a {} by {} image is larger than the {} pixel limit, export as SVG instead",
+            self.width, self.height, PIXEL_LIMIT
+        )
+    }
+}
+
+/// Colour stops for the heatmap, evenly spaced from a single line up to the board's highest
+/// overlap count. Cells without any lines are left black.
+const HEAT_STOPS: [(u8, u8, u8); 5] = [
+    (13, 8, 135),
+    (126, 3, 168),
+    (204, 71, 120),
+    (248, 149, 64),
+    (240, 249, 33),
+];
+
+/// Maps an overlap count onto the heatmap, relative to the highest count on the board.
+fn heat_colour(count: usize, max: usize) -> (u8, u8, u8) {
+    if count == 0 {
+        return (0, 0, 0);
+    }
+
+    if max <= 1 {
+        return HEAT_STOPS[HEAT_STOPS.len() - 1];
+    }
+
+    let scaled = (count - 1) as f64 / (max - 1) as f64 * (HEAT_STOPS.len() - 1) as f64;
+    let lower = (scaled.floor() as usize).min(HEAT_STOPS.len() - 2);
+    let fraction = scaled - lower as f64;
+
+    let (from, to) = (HEAT_STOPS[lower], HEAT_STOPS[lower + 1]);
+    let blend = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * fraction).round() as u8;
+
+    (blend(from.0, to.0), blend(from.1, to.1), blend(from.2, to.2))
+}
+
+fn heat_gray(count: usize, max: usize) -> u8 {
+    match max {
+        0 => 0,
+        _ => (count * 255 / max) as u8,
+    }
+}
+
+fn max_count(board: &Board) -> usize {
+    board.marked_cells().map(|(_, count)| count).max().unwrap_or(0)
+}
+
+/// The size of the image in pixels, refusing images over the limit.
+fn image_size(board: &Board, scale: usize) -> Result<(usize, usize), TooLarge> {
+    let (width, height) = (board.width as u128 * scale as u128, board.height as u128 * scale as u128);
+    if width.checked_mul(height).is_none_or(|pixels| pixels > PIXEL_LIMIT) {
+        return Err(TooLarge { width, height });
+    }
+
+    Ok((width as usize, height as usize))
+}
+
+/// Visits every cell in row order. Each cell becomes `scale` by `scale` pixels.
+fn raster(board: &Board, scale: usize, channels: usize, mut pixel: impl FnMut(usize) -> Vec<u8>) -> Vec<u8> {
+    let mut data = Vec::with_capacity(board.width as usize * board.height as usize * scale * scale * channels);
+
+    for row in 0..board.height {
+        let mut line = Vec::with_capacity(board.width as usize * scale * channels);
+
+        for column in 0..board.width {
+            let point = Point(board.origin.0 + column as i64, board.origin.1 + row as i64);
+            let value = pixel(board.get(&point));
+
+            for _ in 0..scale {
+                line.extend_from_slice(&value);
+            }
+        }
+
+        for _ in 0..scale {
+            data.extend_from_slice(&line);
+        }
+    }
+
+    data
+}
+
+/// Renders the board as a binary PGM, brighter cells having more overlapping lines.
+pub fn to_pgm(board: &Board, scale: usize) -> Result<Vec<u8>, TooLarge> {
+    let (width, height) = image_size(board, scale)?;
+    let max = max_count(board);
+
+    let mut output = format!("P5\n{} {}\n255\n", width, height).into_bytes();
+    output.extend(raster(board, scale, 1, |count| vec![heat_gray(count, max)]));
+
+    Ok(output)
+}
+
+/// Renders the board as a binary PPM coloured with the heatmap scale.
+pub fn to_ppm(board: &Board, scale: usize) -> Result<Vec<u8>, TooLarge> {
+    let (width, height) = image_size(board, scale)?;
+    let max = max_count(board);
+
+    let mut output = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    output.extend(raster(board, scale, 3, |count| {
+        let (r, g, b) = heat_colour(count, max);
+        vec![r, g, b]
+    }));
+
+    Ok(output)
+}
+
+/// Renders the board as an SVG in board coordinates, one unit per cell. When lines are provided
+/// they're drawn through the centers of the cells they cover.
+pub fn to_svg(board: &Board, lines: Option<&[Line]>) -> String {
+    let max = max_count(board);
+    let mut output = String::new();
+
+    writeln!(
+        output,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" shape-rendering=\"crispEdges\">",
+        board.origin.0, board.origin.1, board.width, board.height
+    ).unwrap();
+    writeln!(
+        output,
+        "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#000000\"/>",
+        board.origin.0, board.origin.1, board.width, board.height
+    ).unwrap();
+
+    let mut cells: Vec<(Point, usize)> = board.marked_cells().collect();
+    cells.sort_by_key(|(point, _)| (point.1, point.0));
+
+    for (point, count) in cells.iter() {
+        let (r, g, b) = heat_colour(*count, max);
+        writeln!(
+            output,
+            "  <rect x=\"{}\" y=\"{}\" width=\"1\" height=\"1\" fill=\"#{:02x}{:02x}{:02x}\"><title>{}</title></rect>",
+            point.0, point.1, r, g, b, count
+        ).unwrap();
+    }
+
+    if let Some(lines) = lines {
+        writeln!(output, "  <g stroke=\"#ffffff\" stroke-width=\"0.2\" stroke-linecap=\"round\" opacity=\"0.6\">").unwrap();
+
+        for line in lines.iter() {
+            let center = |value: i64| value as f64 + 0.5;
+
+            writeln!(
+                output,
+                "    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>",
+                center(line.0.0), center(line.0.1), center(line.1.0), center(line.1.1)
+            ).unwrap();
+        }
+
+        writeln!(output, "  </g>").unwrap();
+    }
+
+    writeln!(output, "</svg>").unwrap();
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bounding_box;
+
+    fn sample_board() -> (Board, Vec<Line>) {
+        let lines = vec![Line(Point(0, 0), Point(2, 0)), Line(Point(1, 0), Point(1, 1)), Line(Point(1, 0), Point(2, 1))];
+
+        let mut board = Board::from(bounding_box(&lines));
+        for line in lines.iter() {
+            board.mark_line(line);
+        }
+
+        (board, lines)
+    }
+
+    #[test]
+    fn test_heat_scale() {
+        assert_eq!(heat_colour(0, 5), (0, 0, 0));
+        assert_eq!(heat_colour(1, 5), HEAT_STOPS[0]);
+        assert_eq!(heat_colour(5, 5), HEAT_STOPS[4]);
+        assert_eq!(heat_colour(3, 5), HEAT_STOPS[2]);
+        assert_eq!(heat_colour(1, 1), HEAT_STOPS[4]);
+
+        assert_eq!(heat_gray(0, 3), 0);
+        assert_eq!(heat_gray(3, 3), 255);
+    }
+
+    #[test]
+    fn test_pgm_export() {
+        let (board, _) = sample_board();
+
+        let image = to_pgm(&board, 1).unwrap();
+        assert_eq!(&image[..11], b"P5\n3 2\n255\n");
+        assert_eq!(&image[11..], &[85, 255, 85, 0, 85, 85]);
+
+        let image = to_pgm(&board, 2).unwrap();
+        assert_eq!(&image[..11], b"P5\n6 4\n255\n");
+        assert_eq!(image.len(), 11 + 24);
+        assert_eq!(&image[11..17], &[85, 85, 255, 255, 85, 85]);
+        assert_eq!(&image[11..17], &image[17..23]);
+    }
+
+    #[test]
+    fn test_ppm_export() {
+        let (board, _) = sample_board();
+
+        let image = to_ppm(&board, 1).unwrap();
+        assert_eq!(&image[..11], b"P6\n3 2\n255\n");
+        assert_eq!(image.len(), 11 + 18);
+        assert_eq!(&image[14..17], &[240, 249, 33]);
+        assert_eq!(&image[20..23], &[0, 0, 0]);
+
+        // Sparse boards spanning huge areas are refused rather than rasterized
+        let lines = vec![Line(Point(0, 0), Point(1_000_000, 1_000_000))];
+        let board = Board::from(bounding_box(&lines));
+        assert_eq!(to_ppm(&board, 1), Err(TooLarge { width: 1_000_001, height: 1_000_001 }));
+        assert_eq!(to_pgm(&board, usize::MAX).unwrap_err().width, 1_000_001 * usize::MAX as u128);
+    }
+
+    #[test]
+    fn test_svg_export() {
+        let (board, lines) = sample_board();
+
+        let svg = to_svg(&board, None);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 3 2\""));
+        assert_eq!(svg.matches("<rect").count(), 6);
+        assert!(svg.contains("<rect x=\"1\" y=\"0\" width=\"1\" height=\"1\" fill=\"#f0f921\"><title>3</title></rect>"));
+        assert!(!svg.contains("<line"));
+
+        let svg = to_svg(&board, Some(&lines));
+        assert_eq!(svg.matches("<line").count(), 3);
+        assert!(svg.contains("<line x1=\"1.5\" y1=\"0.5\" x2=\"2.5\" y2=\"1.5\"/>"));
+    }
+}
@@ -0,0 +1,370 @@
+//! Counts the points covered by more than one line straight from the line endpoints, without
+//! marking any cells. The work depends only on the number of lines, not on how far apart their
+//! coordinates are.
+//!
+//! Lines lying on the same infinite line (their carrier) can overlap for whole stretches. These
+//! are handled by sweeping over the lines' extents along their shared carrier. Lines on different
+//! carriers cross at most once and each pair is checked directly. Crossings that land inside an
+//! already counted collinear overlap are skipped so no point is counted twice.
+//!
+//! Every calculation is done with 128 bit integers, which keeps coordinates up to 10^18 either
+//! side of zero exact. Lines reaching further than that are refused rather than risking an
+//! overflow.
+
+use std::collections::HashMap;
+
+use crate::{Line, Point};
+
+/// How far from zero a coordinate can be, in either direction, for the count to stay exact.
+pub const COORDINATE_LIMIT: i64 = 1_000_000_000_000_000_000;
+
+/// Raised for a line with an endpoint beyond `COORDINATE_LIMIT`.
+#[derive(Debug, PartialEq)]
+pub struct OutOfRange {
+    pub point: Point,
+}
+
+impl std::fmt::Display for OutOfRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "point ({}, {}) is too far out to count analytically, coordinates can be at most {} either side of zero",
+            self.point.0, self.point.1, COORDINATE_LIMIT
+        )
+    }
+}
+
+type Coordinate = (i128, i128);
+
+/// A line's primitive direction along with the cross product of that direction and any point on
+/// the line, which together uniquely identify the carrier.
+type CarrierKey = (i128, i128, i128);
+
+struct Segment {
+    start: Coordinate,
+    end: Coordinate,
+    carrier: usize,
+}
+
+struct Carrier {
+    direction: Coordinate,
+    /// Disjoint, sorted, inclusive ranges of positions along the carrier covered by two or more
+    /// lines.
+    overlaps: Vec<(i128, i128)>,
+}
+
+impl Carrier {
+    /// Lattice points on a carrier are exactly one step apart, positions count those steps.
+    fn position(&self, point: Coordinate) -> i128 {
+        let (dx, dy) = self.direction;
+        (point.0 * dx + point.1 * dy).div_euclid(dx * dx + dy * dy)
+    }
+
+    fn overlaps_at(&self, point: Coordinate) -> bool {
+        let position = self.position(point);
+        let idx = self.overlaps.partition_point(|(_, end)| *end < position);
+
+        self.overlaps.get(idx).is_some_and(|(start, _)| *start <= position)
+    }
+}
+
+fn cross(a: Coordinate, b: Coordinate) -> i128 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a.abs()
+}
+
+/// The smallest integer step along the line, oriented so equivalent lines agree on it. A line
+/// that is only a single point is treated as lying on a horizontal carrier.
+fn primitive_direction(start: Coordinate, end: Coordinate) -> Coordinate {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+
+    let divisor = gcd(dx, dy);
+    if divisor == 0 {
+        return (1, 0);
+    }
+
+    let (dx, dy) = (dx / divisor, dy / divisor);
+    if dx < 0 || (dx == 0 && dy < 0) {
+        (-dx, -dy)
+    } else {
+        (dx, dy)
+    }
+}
+
+/// Returns the lattice point the two segments on different carriers share, if any.
+fn crossing_point(a: &Segment, b: &Segment) -> Option<Coordinate> {
+    let r = (a.end.0 - a.start.0, a.end.1 - a.start.1);
+    let s = (b.end.0 - b.start.0, b.end.1 - b.start.1);
+
+    // Single points can't be intersected as segments, check whether they sit on the other line
+    if r == (0, 0) {
+        return on_segment(a.start, b).then_some(a.start);
+    }
+
+    if s == (0, 0) {
+        return on_segment(b.start, a).then_some(b.start);
+    }
+
+    let denominator = cross(r, s);
+    if denominator == 0 {
+        return None;
+    }
+
+    let offset = (b.start.0 - a.start.0, b.start.1 - a.start.1);
+    let (t, u) = (cross(offset, s), cross(offset, r));
+
+    // Both parameters need to fall within 0..=1 once divided by the denominator
+    let (t, u, denominator) = if denominator < 0 { (-t, -u, -denominator) } else { (t, u, denominator) };
+    if t < 0 || t > denominator || u < 0 || u > denominator {
+        return None;
+    }
+
+    // Reducing the fraction first keeps every product within the length of the segment. Once
+    // `t` and the denominator share no factor the point is on the lattice only when the
+    // denominator divides both components of the direction.
+    let divisor = gcd(t, denominator).max(1);
+    let (t, denominator) = (t / divisor, denominator / divisor);
+    if r.0 % denominator != 0 || r.1 % denominator != 0 {
+        return None;
+    }
+
+    Some((a.start.0 + r.0 / denominator * t, a.start.1 + r.1 / denominator * t))
+}
+
+fn on_segment(point: Coordinate, segment: &Segment) -> bool {
+    let direction = (segment.end.0 - segment.start.0, segment.end.1 - segment.start.1);
+    let offset = (point.0 - segment.start.0, point.1 - segment.start.1);
+
+    cross(direction, offset) == 0
+        && segment.start.0.min(segment.end.0) <= point.0
+        && point.0 <= segment.start.0.max(segment.end.0)
+        && segment.start.1.min(segment.end.1) <= point.1
+        && point.1 <= segment.start.1.max(segment.end.1)
+}
+
+/// Finds the stretches covered at least twice from inclusive extents along a carrier.
+fn collinear_overlaps(extents: &[(i128, i128)]) -> Vec<(i128, i128)> {
+    let mut events: Vec<(i128, i32)> = extents.iter()
+        .flat_map(|(start, end)| [(*start, 1), (*end + 1, -1)])
+        .collect();
+    events.sort_unstable();
+
+    let mut overlaps: Vec<(i128, i128)> = vec![];
+    let mut coverage = 0;
+    let mut overlap_start = None;
+
+    for (position, change) in events {
+        coverage += change;
+
+        match (coverage >= 2, overlap_start) {
+            (true, None) => overlap_start = Some(position),
+            (false, Some(start)) => {
+                // Overlaps that touch are merged so each position appears in only one range
+                match overlaps.last_mut() {
+                    Some(last) if last.1 + 1 == start => last.1 = position - 1,
+                    _ => overlaps.push((start, position - 1)),
+                }
+
+                overlap_start = None;
+            }
+            _ => (),
+        }
+    }
+
+    overlaps
+}
+
+/// Counts the lattice points covered by at least two lines.
+pub fn overlapping_point_count(lines: &[Line]) -> Result<usize, OutOfRange> {
+    let out_of_range = |point: &Point| point.0.unsigned_abs().max(point.1.unsigned_abs()) > COORDINATE_LIMIT as u64;
+    if let Some(point) = lines.iter().flat_map(|line| [line.0, line.1]).find(out_of_range) {
+        return Err(OutOfRange { point });
+    }
+
+    let mut carrier_ids: HashMap<CarrierKey, usize> = HashMap::new();
+    let mut carriers: Vec<Carrier> = vec![];
+    let mut extents: Vec<Vec<(i128, i128)>> = vec![];
+    let mut segments: Vec<Segment> = vec![];
+
+    for line in lines.iter() {
+        let start = (line.0.0 as i128, line.0.1 as i128);
+        let end = (line.1.0 as i128, line.1.1 as i128);
+
+        let direction = primitive_direction(start, end);
+        let key = (direction.0, direction.1, cross(direction, start));
+
+        let carrier = *carrier_ids.entry(key).or_insert_with(|| {
+            carriers.push(Carrier { direction, overlaps: vec![] });
+            extents.push(vec![]);
+            carriers.len() - 1
+        });
+
+        let (a, b) = (carriers[carrier].position(start), carriers[carrier].position(end));
+        extents[carrier].push((a.min(b), a.max(b)));
+
+        segments.push(Segment { start, end, carrier });
+    }
+
+    let mut count: i128 = 0;
+    for (carrier, carrier_extents) in carriers.iter_mut().zip(extents.iter()) {
+        carrier.overlaps = collinear_overlaps(carrier_extents);
+        count += carrier.overlaps.iter().map(|(start, end)| end - start + 1).sum::<i128>();
+    }
+
+    let mut crossings: HashMap<Coordinate, Vec<usize>> = HashMap::new();
+    for (idx, a) in segments.iter().enumerate() {
+        for b in segments[(idx + 1)..].iter().filter(|b| b.carrier != a.carrier) {
+            if let Some(point) = crossing_point(a, b) {
+                crossings.entry(point).or_default().extend([a.carrier, b.carrier]);
+            }
+        }
+    }
+
+    // Any collinear overlap through a crossing point necessarily involves a line that took part
+    // in one of the crossings found there, so only those carriers need checking. A crossing
+    // outside every overlap is a new point, while one inside several overlaps has already been
+    // counted once for each of them.
+    for (point, point_carriers) in crossings.iter_mut() {
+        point_carriers.sort_unstable();
+        point_carriers.dedup();
+
+        match point_carriers.iter().filter(|c| carriers[**c].overlaps_at(*point)).count() {
+            0 => count += 1,
+            overlapping => count -= overlapping as i128 - 1,
+        }
+    }
+
+    Ok(count as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use common::Rng;
+
+    use crate::tests::REFERENCE_INPUT;
+    use crate::{bounding_box, parse_vent_lines, Board, Point};
+
+    fn grid_count(lines: &[Line]) -> usize {
+        let mut board = Board::from(bounding_box(lines));
+        for line in lines.iter() {
+            board.mark_line(line);
+        }
+
+        board.overlapping_position_count()
+    }
+
+    #[test]
+    fn test_reference_counts() {
+        let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
+        let lines = parse_vent_lines(input).unwrap();
+
+        let straight: Vec<Line> = lines.iter().filter(|l| l.is_straight()).cloned().collect();
+        assert_eq!(overlapping_point_count(&straight).unwrap(), 5);
+        assert_eq!(overlapping_point_count(&lines).unwrap(), 12);
+    }
+
+    #[test]
+    fn test_collinear_overlaps() {
+        assert_eq!(collinear_overlaps(&[(0, 5), (3, 8), (4, 10)]), vec![(3, 8)]);
+        assert_eq!(collinear_overlaps(&[(0, 2), (2, 4), (4, 6)]), vec![(2, 2), (4, 4)]);
+        assert_eq!(collinear_overlaps(&[(0, 3), (0, 3), (4, 6), (4, 6)]), vec![(0, 6)]);
+        assert!(collinear_overlaps(&[(0, 3), (5, 6)]).is_empty());
+    }
+
+    #[test]
+    fn test_matches_grid_on_random_lines() {
+        let mut rng = Rng::new(5);
+
+        for _ in 0..500 {
+            let line_count = 2 + rng.below(30);
+            let lines: Vec<Line> = (0..line_count)
+                .map(|_| {
+                    let mut coordinate = || rng.below(12) as i64 - 6;
+                    let start = Point(coordinate(), coordinate());
+
+                    // Mostly the puzzle's own line shapes, with some arbitrary slopes mixed in
+                    let end = match rng.below(4) {
+                        0 => Point(start.0, rng.below(12) as i64 - 6),
+                        1 => Point(rng.below(12) as i64 - 6, start.1),
+                        2 => {
+                            let length = rng.below(8) as i64;
+                            let direction = if rng.below(2) == 0 { 1 } else { -1 };
+                            Point(start.0 + length, start.1 + direction * length)
+                        }
+                        _ => Point(rng.below(12) as i64 - 6, rng.below(12) as i64 - 6),
+                    };
+
+                    Line(start, end)
+                })
+                .collect();
+
+            assert_eq!(overlapping_point_count(&lines).unwrap(), grid_count(&lines), "lines: {:?}", lines);
+        }
+    }
+
+    #[test]
+    fn test_large_coordinates() {
+        let e = 1_000_000_000_000_000_000;
+
+        let lines = vec![Line(Point(-e, -e), Point(e, e)), Line(Point(-e, e), Point(e, -e))];
+        assert_eq!(overlapping_point_count(&lines).unwrap(), 1);
+
+        // Crosses at (3 * 10^17, 10^17)
+        let lines = vec![
+            Line(Point(0, 0), Point(e - 1, e / 3)),
+            Line(Point(0, 4 * e / 10), Point(4 * e / 10, 0)),
+        ];
+        assert_eq!(overlapping_point_count(&lines).unwrap(), 1);
+
+        // Coordinates at the very ends of the signed range would overflow the cross products
+        let lines = vec![Line(Point(i64::MIN, i64::MIN), Point(i64::MAX, i64::MAX)), Line(Point(i64::MIN, i64::MAX), Point(i64::MAX, i64::MIN))];
+        assert_eq!(overlapping_point_count(&lines), Err(OutOfRange { point: Point(i64::MIN, i64::MIN) }));
+
+        let lines = vec![Line(Point(-e, 0), Point(e, 0)), Line(Point(0, -e), Point(0, e + 1))];
+        assert_eq!(overlapping_point_count(&lines), Err(OutOfRange { point: Point(0, e + 1) }));
+
+        // Nearly parallel lines crossing between lattice points
+        for e in [1_000_000_000_000_000, e] {
+            let lines = vec![Line(Point(0, 0), Point(e, e - 1)), Line(Point(0, e), Point(e - 3, 1))];
+            assert_eq!(overlapping_point_count(&lines).unwrap(), 0);
+        }
+    }
+
+    #[test]
+    fn test_crossing_overlaps() {
+        // Two overlapping horizontal lines crossed by two overlapping vertical lines share a
+        // single point between both overlaps
+        let lines = vec![
+            Line(Point(0, 5), Point(10, 5)),
+            Line(Point(2, 5), Point(8, 5)),
+            Line(Point(4, 0), Point(4, 10)),
+            Line(Point(4, 3), Point(4, 7)),
+        ];
+
+        assert_eq!(overlapping_point_count(&lines).unwrap(), 7 + 5 - 1);
+        assert_eq!(overlapping_point_count(&lines).unwrap(), grid_count(&lines));
+    }
+
+    #[test]
+    fn test_huge_coordinates() {
+        let lines = vec![
+            Line(Point(0, 0), Point(1_000_000_000, 1_000_000_000)),
+            Line(Point(500_000_000, 500_000_000), Point(2_000_000_000, 2_000_000_000)),
+            Line(Point(0, 1_000_000_000), Point(1_000_000_000, 0)),
+            Line(Point(750_000_000, 0), Point(750_000_000, 2_000_000_000)),
+        ];
+
+        // The first two overlap along 500,000,001 points. The anti-diagonal and the vertical line
+        // both cross the diagonal within that stretch, leaving only their own crossing to add.
+        assert_eq!(overlapping_point_count(&lines).unwrap(), 500_000_001 + 1);
+    }
+}
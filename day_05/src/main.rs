@@ -1,121 +1,294 @@
 #![allow(unused_variables)]
 
+mod export;
+mod intersections;
+
+use std::collections::{BTreeMap, HashMap};
+
 use common::read_puzzle_input;
 
+/// Boards covering more cells than this use sparse storage when the storage mode is automatic.
+/// Dense storage at this size is roughly 32MiB.
+const DENSE_CELL_LIMIT: usize = 4_000_000;
+
+/// A board only covers the bounding box of the lines marked on it. The origin is the top left
+/// corner of that box and every position is stored relative to it. Marked lines are kept in the
+/// order they were marked, their index in that list is the line's id.
 #[derive(Debug, PartialEq)]
 struct Board {
-    positions: Vec<Vec<usize>>,
+    origin: Point,
+    width: u64,
+    height: u64,
+    positions: Positions,
+    lines: Vec<Line>,
+}
+
+/// Dense storage keeps a counter for every cell on the board, while sparse storage only tracks
+/// cells at least one line passes through. Sparse storage is far slower per cell but its size
+/// depends on the lines rather than the extent of the board.
+#[derive(Debug, PartialEq)]
+enum Positions {
+    Dense(Vec<Vec<usize>>),
+    Sparse(HashMap<Point, usize>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StorageMode {
+    Auto,
+    Dense,
+    Sparse,
 }
 
 impl Board {
-    fn mark_line(&mut self, line: &Line) {
-        if line.slope().is_infinite() {
-            let x_pos = line.left().0;
-            for y_pos in line.bottom().1..=line.top().1 {
-                self.positions[y_pos][x_pos] += 1;
-            }
+    fn new((min, max): (Point, Point), mode: StorageMode) -> Self {
+        // The full span of an i64 is one more than a u64 can hold, saturate rather than overflow
+        let width = max.0.abs_diff(min.0).saturating_add(1);
+        let height = max.1.abs_diff(min.1).saturating_add(1);
+
+        let use_dense = match mode {
+            StorageMode::Auto => width.checked_mul(height).is_some_and(|cells| cells <= DENSE_CELL_LIMIT as u64),
+            StorageMode::Dense => true,
+            StorageMode::Sparse => false,
+        };
+
+        let positions = if use_dense {
+            Positions::Dense(vec![vec![0; width as usize]; height as usize])
         } else {
-            for x_pos in line.left().0..=line.right().0 {
-                let y_pos = line.solve_for_y(x_pos);
-                self.positions[y_pos][x_pos] += 1;
+            Positions::Sparse(HashMap::new())
+        };
+
+        Board { origin: min, width, height, positions, lines: vec![] }
+    }
+
+    /// Converts a point into row and column indexes relative to the board's origin.
+    fn offset(&self, point: &Point) -> (usize, usize) {
+        ((point.1 - self.origin.1) as usize, (point.0 - self.origin.0) as usize)
+    }
+
+    fn get(&self, point: &Point) -> usize {
+        match &self.positions {
+            Positions::Dense(rows) => {
+                let (row, col) = self.offset(point);
+                rows[row][col]
             }
+            Positions::Sparse(cells) => cells.get(point).copied().unwrap_or(0),
         }
     }
 
-    fn overlapping_position_count(&self) -> usize {
-        let mut count = 0;
+    #[cfg(test)]
+    fn is_sparse(&self) -> bool {
+        matches!(self.positions, Positions::Sparse(_))
+    }
+
+    /// Marks every point on the line and returns the id the line was given.
+    fn mark_line(&mut self, line: &Line) -> usize {
+        let origin = self.origin;
 
-        for row in self.positions.iter() {
-            for position in row.iter() {
-                if *position > 1 {
-                    count += 1;
+        match &mut self.positions {
+            Positions::Dense(rows) => {
+                for Point(x_pos, y_pos) in line.points() {
+                    rows[(y_pos - origin.1) as usize][(x_pos - origin.0) as usize] += 1;
+                }
+            }
+            Positions::Sparse(cells) => {
+                for point in line.points() {
+                    *cells.entry(point).or_insert(0) += 1;
                 }
             }
         }
 
-        count
+        self.lines.push(line.clone());
+        self.lines.len() - 1
+    }
+
+    /// Every position with at least one line passing through it, along with its count. The order
+    /// depends on the storage in use.
+    fn marked_cells(&self) -> Box<dyn Iterator<Item = (Point, usize)> + '_> {
+        match &self.positions {
+            Positions::Dense(rows) => Box::new(rows.iter().enumerate().flat_map(move |(row, positions)| {
+                positions.iter()
+                    .enumerate()
+                    .filter(|(_, count)| **count > 0)
+                    .map(move |(column, count)| (Point(self.origin.0 + column as i64, self.origin.1 + row as i64), *count))
+            })),
+            Positions::Sparse(cells) => Box::new(cells.iter().map(|(point, count)| (*point, *count))),
+        }
+    }
+
+    fn overlapping_position_count(&self) -> usize {
+        self.count_at_least(2)
+    }
+
+    /// Counts the positions covered by at least `k` lines. Positions without any lines are never
+    /// counted, so a `k` of zero counts the same positions as one.
+    fn count_at_least(&self, k: usize) -> usize {
+        let k = k.max(1);
+
+        match &self.positions {
+            Positions::Dense(rows) => rows.iter().flatten().filter(|position| **position >= k).count(),
+            Positions::Sparse(cells) => cells.values().filter(|position| **position >= k).count(),
+        }
+    }
+
+    /// Maps each number of overlapping lines to how many positions have exactly that many.
+    fn histogram(&self) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+        for (_, count) in self.marked_cells() {
+            *histogram.entry(count).or_insert(0) += 1;
+        }
+
+        histogram
+    }
+
+    /// The most lines covering any single position along with every position reaching it, in
+    /// row order. Returns `None` when nothing has been marked.
+    fn max_overlap(&self) -> Option<(usize, Vec<Point>)> {
+        let max = self.marked_cells().map(|(_, count)| count).max()?;
+
+        let mut points: Vec<Point> = self.marked_cells()
+            .filter(|(_, count)| *count == max)
+            .map(|(point, _)| point)
+            .collect();
+        points.sort_by_key(|point| (point.1, point.0));
+
+        Some((max, points))
+    }
+
+    /// The ids of every marked line passing through the point, in the order they were marked.
+    fn lines_through(&self, point: &Point) -> Vec<usize> {
+        self.lines.iter()
+            .enumerate()
+            .filter(|(_, line)| line.contains(point))
+            .map(|(id, _)| id)
+            .collect()
     }
 }
 
+/// Draws the board with its y coordinates down the left side and an x coordinate above the
+/// first column and every column that is a multiple of ten, as long as the labels don't collide.
 impl std::fmt::Display for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in self.positions.iter() {
-            for position in row.iter() {
-                if *position == 0 {
+        let max_y = self.origin.1 + self.height as i64 - 1;
+        let margin = self.origin.1.to_string().len().max(max_y.to_string().len());
+
+        let mut x_axis = String::new();
+        for column in 0..self.width {
+            let x_pos = self.origin.0 + column as i64;
+
+            if column == 0 || (x_pos % 10 == 0 && x_axis.len() < column as usize) {
+                x_axis.push_str(&" ".repeat(column as usize - x_axis.len()));
+                x_axis.push_str(&x_pos.to_string());
+            }
+        }
+        writeln!(f, "{} {}", " ".repeat(margin), x_axis)?;
+
+        for row in 0..self.height {
+            let y_pos = self.origin.1 + row as i64;
+            write!(f, "{:>margin$} ", y_pos, margin = margin)?;
+
+            for column in 0..self.width {
+                let position = self.get(&Point(self.origin.0 + column as i64, y_pos));
+
+                if position == 0 {
                     write!(f, ".")?;
                 } else {
                     write!(f, "{}", position)?;
                 }
             }
 
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
 
         Ok(())
     }
 }
 
-impl From<(usize, usize)> for Board {
-    fn from((width, height): (usize, usize)) -> Self {
-        Board {
-            positions: vec![vec![0; width]; height],
-        }
+impl From<(Point, Point)> for Board {
+    fn from(bounds: (Point, Point)) -> Self {
+        Board::new(bounds, StorageMode::Auto)
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 struct Line(Point, Point);
 
 impl Line {
-    fn bottom(&self) -> &Point {
-        if self.0.1 >= self.1.1 {
-            &self.1
-        } else {
-            &self.0
+    /// Walks every point with integer coordinates that lies exactly on the line, from the first
+    /// point to the second. The step between points is the line's direction reduced by the
+    /// greatest common divisor of its components so no rounding is ever involved. Lines that
+    /// aren't horizontal, vertical or diagonal only pass through some of the cells they cross.
+    fn points(&self) -> LinePoints {
+        // Lines between extreme coordinates span more than an i64 can hold
+        let delta_x = self.1.0 as i128 - self.0.0 as i128;
+        let delta_y = self.1.1 as i128 - self.0.1 as i128;
+
+        let steps = gcd(delta_x.unsigned_abs() as u64, delta_y.unsigned_abs() as u64);
+        let (step_x, step_y) = match steps {
+            0 => (0, 0),
+            _ => (delta_x / steps as i128, delta_y / steps as i128),
+        };
+
+        LinePoints {
+            position: self.0,
+            step: (step_x, step_y),
+            remaining: steps as u128 + 1,
         }
     }
+}
 
-    fn left(&self) -> &Point {
-        if self.0.0 >= self.1.0 {
-            &self.1
-        } else {
-            &self.0
-        }
-    }
+struct LinePoints {
+    position: Point,
+    step: (i128, i128),
+    remaining: u128,
+}
 
-    fn right(&self) -> &Point {
-        if self.0.0 >= self.1.0 {
-            &self.0
-        } else {
-            &self.1
+impl Iterator for LinePoints {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        if self.remaining == 0 {
+            return None;
         }
-    }
 
-    fn top(&self) -> &Point {
-        if self.0.1 >= self.1.1 {
-            &self.0
-        } else {
-            &self.1
+        let point = self.position;
+        self.remaining -= 1;
+
+        // The step past the last point can leave the range of an i64, so it's never taken
+        if self.remaining > 0 {
+            self.position = Point((point.0 as i128 + self.step.0) as i64, (point.1 as i128 + self.step.1) as i64);
         }
-    }
-}
 
-impl Line {
-    fn solve_for_y(&self, x: usize) -> usize {
-        let y_value = self.slope() * x as f64 + self.y_intercept() as f64;
-        y_value.round() as usize
+        Some(point)
     }
 
-    fn slope(&self) -> f64 {
-        (self.right().1 as f64 - self.left().1 as f64) / (self.right().0 as f64 - self.left().0 as f64)
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::try_from(self.remaining).unwrap_or(usize::MAX), usize::try_from(self.remaining).ok())
     }
+}
 
-    fn y_intercept(&self) -> f64 {
-        -1.0 * ((self.slope() * self.left().0 as f64) - self.left().1 as f64)
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
     }
+
+    a
 }
 
 impl Line {
+    /// Whether the point is one of the points visited by `points`. Any point with integer
+    /// coordinates lying on the segment is a whole number of steps from the start, so being
+    /// collinear and within the line's extent is enough.
+    fn contains(&self, point: &Point) -> bool {
+        let direction = (self.1.0 as i128 - self.0.0 as i128, self.1.1 as i128 - self.0.1 as i128);
+        let offset = (point.0 as i128 - self.0.0 as i128, point.1 as i128 - self.0.1 as i128);
+
+        direction.0 * offset.1 == direction.1 * offset.0
+            && self.0.0.min(self.1.0) <= point.0
+            && point.0 <= self.0.0.max(self.1.0)
+            && self.0.1.min(self.1.1) <= point.1
+            && point.1 <= self.0.1.max(self.1.1)
+    }
+
     fn is_straight(&self) -> bool {
         self.is_horizontal() || self.is_vertical()
     }
@@ -129,24 +302,112 @@ impl Line {
     }
 }
 
-#[derive(Debug, PartialEq)]
-struct Point(usize, usize);
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct Point(i64, i64);
+
+/// Returns the top left and bottom right corners of the smallest box containing every line. With
+/// no lines the box is just the origin.
+fn bounding_box(lines: &[Line]) -> (Point, Point) {
+    let mut points = lines.iter().flat_map(|l| [l.0, l.1]);
 
-fn board_size(lines: &Vec<Line>) -> (usize, usize) {
-    (max_width(lines) + 1, max_height(lines) + 1)
+    let first = match points.next() {
+        Some(point) => point,
+        None => return (Point(0, 0), Point(0, 0)),
+    };
+
+    points.fold((first, first), |(min, max), p| {
+        (Point(min.0.min(p.0), min.1.min(p.1)), Point(max.0.max(p.0), max.1.max(p.1)))
+    })
 }
 
 fn main() {
     let input_entries = read_puzzle_input(5);
 
-    let second_lines: Vec<Line> = parse_vent_lines(input_entries).into_iter().collect();
-    let mut second_board = Board::from(board_size(&second_lines));
+    // The storage can be forced with `--dense` or `--sparse`, otherwise it is picked based on
+    // the size of the board. `--analytic` counts overlaps from the line endpoints instead. The
+    // board with every line can be exported with `--pgm`, `--ppm` or `--svg` followed by a path,
+    // `--scale` sets the pixels per cell for the raster formats and `--svg-lines` draws the
+    // lines themselves over the SVG. `--stats` describes how the lines overlap on the board with
+    // every line.
+    let mut mode = StorageMode::Auto;
+    let mut analytic = false;
+    let mut exports: Vec<(String, String)> = vec![];
+    let mut scale = 1;
+    let mut svg_lines = false;
+    let mut stats = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dense" => mode = StorageMode::Dense,
+            "--sparse" => mode = StorageMode::Sparse,
+            "--analytic" => analytic = true,
+            "--pgm" | "--ppm" | "--svg" => exports.push((arg.clone(), args.next().expect("missing export path"))),
+            "--scale" => scale = args.next().and_then(|s| s.parse().ok()).expect("invalid export scale"),
+            "--svg-lines" => svg_lines = true,
+            "--stats" => stats = true,
+            _ => (),
+        }
+    }
+
+    let second_lines = match parse_vent_lines(input_entries) {
+        Ok(lines) => lines,
+        Err(errors) => {
+            for error in errors.iter() {
+                eprintln!("{}", error);
+            }
+
+            std::process::exit(1);
+        }
+    };
+
+    if analytic {
+        let first_lines: Vec<Line> = second_lines.iter().filter(|l| l.is_straight()).cloned().collect();
+
+        match (intersections::overlapping_point_count(&first_lines), intersections::overlapping_point_count(&second_lines)) {
+            (Ok(first), Ok(second)) => {
+                println!("first answer: {}", first);
+                println!("second answer: {}", second);
+            }
+            (Err(err), _) | (_, Err(err)) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let mut second_board = Board::new(bounding_box(&second_lines), mode);
     for line in second_lines.iter() {
         second_board.mark_line(line);
     }
 
+    for (format, path) in exports.iter() {
+        let data = match format.as_str() {
+            "--pgm" => export::to_pgm(&second_board, scale),
+            "--ppm" => export::to_ppm(&second_board, scale),
+            _ => Ok(export::to_svg(&second_board, svg_lines.then_some(second_lines.as_slice())).into_bytes()),
+        };
+
+        let data = match data {
+            Ok(data) => data,
+            Err(err) => {
+                eprintln!("unable to export {}: {}", path, err);
+                continue;
+            }
+        };
+
+        if let Err(err) = std::fs::write(path, data) {
+            eprintln!("unable to write {}: {}", path, err);
+        }
+    }
+
+    if stats {
+        print_stats(&second_board);
+    }
+
     let first_lines: Vec<Line> = second_lines.into_iter().filter(|l| l.is_straight()).collect();
-    let mut first_board = Board::from(board_size(&first_lines));
+    let mut first_board = Board::new(bounding_box(&first_lines), mode);
     for line in first_lines.iter() {
         first_board.mark_line(line);
     }
@@ -155,82 +416,127 @@ fn main() {
     println!("second answer: {}", second_board.overlapping_position_count());
 }
 
-fn max_height(lines: &Vec<Line>) -> usize {
-    let mut max_height = 0;
+fn print_stats(board: &Board) {
+    let histogram: Vec<String> = board.histogram().iter().map(|(lines, count)| format!("{}: {}", lines, count)).collect();
+    println!("positions by overlapping lines: {}", histogram.join(", "));
 
-    for l in lines.iter() {
-        if l.0.1 > max_height {
-            max_height = l.0.1;
+    if let Some((max, points)) = board.max_overlap() {
+        println!("most overlapping lines: {}", max);
+        for point in points.iter() {
+            println!("  ({}, {}) covered by lines {:?}", point.0, point.1, board.lines_through(point));
         }
+    }
+}
 
-        if l.1.1 > max_height {
-            max_height = l.1.1;
+#[derive(Debug, PartialEq)]
+enum ParseError {
+    PointCount { line: usize, found: usize },
+    Arity { line: usize, point: String, found: usize },
+    InvalidCoordinate { line: usize, value: String },
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::PointCount { line, found } => write!(f, "line {}: expected 2 points separated by '->' but found {}", line, found),
+            ParseError::Arity { line, point, found } => write!(
+                f,
+                "line {}: point '{}' has {} coordinates instead of 2",
+                line, point, found
+            ),
+            ParseError::InvalidCoordinate { line, value } => write!(f, "line {}: '{}' is not a valid coordinate", line, value),
         }
     }
-
-    max_height
 }
 
-fn max_width(lines: &Vec<Line>) -> usize {
-    let mut max_width = 0;
+/// Parses lines of the form `x1,y1 -> x2,y2`. Whitespace around the separators and coordinates
+/// is ignored, as are blank lines. Every problem in the input is collected rather than stopping
+/// at the first one, each with its one based line number.
+fn parse_vent_lines(lines: Vec<String>) -> Result<Vec<Line>, Vec<ParseError>> {
+    let mut output_lines = vec![];
+    let mut errors = vec![];
 
-    for l in lines.iter() {
-        if l.0.0 > max_width {
-            max_width = l.0.0;
+    for (line_idx, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
         }
 
-        if l.1.0 > max_width {
-            max_width = l.1.0;
+        let line_number = line_idx + 1;
+        let points: Vec<&str> = line.split("->").map(|p| p.trim()).collect();
+
+        if points.len() != 2 {
+            errors.push(ParseError::PointCount { line: line_number, found: points.len() });
+            continue;
+        }
+
+        let parsed: Vec<Option<Point>> = points.iter().map(|p| parse_point(p, line_number, &mut errors)).collect();
+
+        if let [Some(source), Some(target)] = parsed[..] {
+            output_lines.push(Line(source, target));
         }
     }
 
-    max_width
+    match errors.is_empty() {
+        true => Ok(output_lines),
+        false => Err(errors),
+    }
 }
 
-fn parse_vent_lines(lines: Vec<String>) -> Vec<Line> {
-    let mut output_lines = vec![];
-
-    for line in lines.iter() {
-        let points: Vec<&str> = line.trim().split(" -> ").collect();
+fn parse_point(point: &str, line: usize, errors: &mut Vec<ParseError>) -> Option<Point> {
+    let components: Vec<&str> = point.split(',').map(|c| c.trim()).collect();
 
-        if points.len() != 2 { panic!("invalid number of points on a line"); }
+    if components.len() != 2 {
+        errors.push(ParseError::Arity { line, point: point.to_string(), found: components.len() });
+        return None;
+    }
 
-        let source_point: Vec<usize> = points[0].split(',').map(|p| p.parse().unwrap()).collect();
-        let target_point: Vec<usize> = points[1].split(',').map(|p| p.parse().unwrap()).collect();
+    let mut coordinates = [0; 2];
+    let mut valid = true;
 
-        output_lines.push(Line(Point(source_point[0], source_point[1]), Point(target_point[0], target_point[1])));
+    for (coordinate, component) in coordinates.iter_mut().zip(components.iter()) {
+        match component.parse() {
+            Ok(value) => *coordinate = value,
+            Err(_) => {
+                errors.push(ParseError::InvalidCoordinate { line, value: component.to_string() });
+                valid = false;
+            }
+        }
     }
 
-    output_lines
+    valid.then_some(Point(coordinates[0], coordinates[1]))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const REFERENCE_INPUT: &str = "0,9 -> 5,9\n\
-                                   8,0 -> 0,8\n\
-                                   9,4 -> 3,4\n\
-                                   2,2 -> 2,1\n\
-                                   7,0 -> 7,4\n\
-                                   6,4 -> 2,0\n\
-                                   0,9 -> 2,9\n\
-                                   3,4 -> 1,4\n\
-                                   0,0 -> 8,8\n\
-                                   5,5 -> 8,2";
+    pub(crate) const REFERENCE_INPUT: &str = "0,9 -> 5,9\n\
+                                              8,0 -> 0,8\n\
+                                              9,4 -> 3,4\n\
+                                              2,2 -> 2,1\n\
+                                              7,0 -> 7,4\n\
+                                              6,4 -> 2,0\n\
+                                              0,9 -> 2,9\n\
+                                              3,4 -> 1,4\n\
+                                              0,0 -> 8,8\n\
+                                              5,5 -> 8,2";
 
     #[test]
-    fn test_board_size() {
+    fn test_bounding_box() {
         let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
-        let lines = parse_vent_lines(input);
+        let lines = parse_vent_lines(input).unwrap();
 
-        assert_eq!(board_size(&lines), (10, 10));
+        assert_eq!(bounding_box(&lines), (Point(0, 0), Point(9, 9)));
+
+        let lines = vec![Line(Point(-4, 12), Point(3, 12)), Line(Point(1, 8), Point(1, 20))];
+        assert_eq!(bounding_box(&lines), (Point(-4, 8), Point(3, 20)));
+        assert_eq!(bounding_box(&[]), (Point(0, 0), Point(0, 0)));
     }
 
     #[test]
     fn test_line_parser() {
         let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
-        let lines = parse_vent_lines(input);
+        let lines = parse_vent_lines(input).unwrap();
 
         let expected_lines = vec![
             Line(Point(0, 9), Point(5, 9)),
@@ -249,19 +555,69 @@ mod tests {
     }
 
     #[test]
-    fn test_line_solutions() {
+    fn test_line_parser_errors() {
+        let input: Vec<String> = "  0,9->5,9\n\n8 , 0 ->   0,8  ".lines().map(|e| e.to_string()).collect();
+        assert_eq!(parse_vent_lines(input), Ok(vec![Line(Point(0, 9), Point(5, 9)), Line(Point(8, 0), Point(0, 8))]));
+
+        let input: Vec<String> = "1,2,3 -> 4,5\n1,2 -> 3,4 -> 5,6\n1,x -> y,2\n7\n-1,-2 -> 3,4".lines().map(|e| e.to_string()).collect();
+        let errors = parse_vent_lines(input).unwrap_err();
+
+        assert_eq!(errors, vec![
+            ParseError::Arity { line: 1, point: "1,2,3".to_string(), found: 3 },
+            ParseError::PointCount { line: 2, found: 3 },
+            ParseError::InvalidCoordinate { line: 3, value: "x".to_string() },
+            ParseError::InvalidCoordinate { line: 3, value: "y".to_string() },
+            ParseError::PointCount { line: 4, found: 1 },
+        ]);
+        assert_eq!(errors[0].to_string(), "line 1: point '1,2,3' has 3 coordinates instead of 2");
+    }
+
+    #[test]
+    fn test_line_points() {
         let line = Line(Point(3, 5), Point(6, 11));
-        assert_eq!(line.slope(), 2.0);
-        assert_eq!(line.y_intercept(), -1.0);
-        assert_eq!(line.solve_for_y(9), 17);
+        assert_eq!(line.points().collect::<Vec<Point>>(), vec![Point(3, 5), Point(4, 7), Point(5, 9), Point(6, 11)]);
+
+        let line = Line(Point(3, 5), Point(9, 9));
+        assert_eq!(line.points().collect::<Vec<Point>>(), vec![Point(3, 5), Point(6, 7), Point(9, 9)]);
+
+        let line = Line(Point(2, 2), Point(2, 5));
+        assert_eq!(line.points().collect::<Vec<Point>>(), vec![Point(2, 2), Point(2, 3), Point(2, 4), Point(2, 5)]);
+
+        let line = Line(Point(9, 4), Point(7, 4));
+        assert_eq!(line.points().collect::<Vec<Point>>(), vec![Point(9, 4), Point(8, 4), Point(7, 4)]);
+
+        let line = Line(Point(8, 0), Point(6, 2));
+        assert_eq!(line.points().collect::<Vec<Point>>(), vec![Point(8, 0), Point(7, 1), Point(6, 2)]);
+
+        let line = Line(Point(4, 4), Point(4, 4));
+        assert_eq!(line.points().collect::<Vec<Point>>(), vec![Point(4, 4)]);
+
+        // Large coordinates stay exact where floating point slopes would drift
+        let line = Line(Point(0, 1), Point(3_000_000_000, 2_000_000_001));
+        let mut points = line.points();
+        assert_eq!(points.size_hint(), (1_000_000_001, Some(1_000_000_001)));
+        assert_eq!(points.nth(1), Some(Point(3, 3)));
+
+        let line = Line(Point(3_000_000_001, 2_000_000_000), Point(2_000_000_001, 1_000_000_001));
+        assert_eq!(line.points().collect::<Vec<Point>>(), vec![Point(3_000_000_001, 2_000_000_000), Point(2_000_000_001, 1_000_000_001)]);
+
+        // Lines spanning the whole signed range have more steps than an i64 can count
+        let line = Line(Point(i64::MIN, 0), Point(i64::MAX, 1));
+        assert_eq!(line.points().collect::<Vec<Point>>(), vec![Point(i64::MIN, 0), Point(i64::MAX, 1)]);
+
+        let line = Line(Point(i64::MAX, i64::MAX), Point(i64::MIN, i64::MIN));
+        let mut points = line.points();
+        assert_eq!(points.size_hint(), (usize::MAX, None));
+        assert_eq!(points.nth(1), Some(Point(i64::MAX - 1, i64::MAX - 1)));
+        assert!(line.contains(&Point(0, 0)));
     }
 
     #[test]
     fn test_first_part() {
         let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
-        let lines: Vec<Line> = parse_vent_lines(input).into_iter().filter(|l| l.is_straight()).collect();
+        let lines: Vec<Line> = parse_vent_lines(input).unwrap().into_iter().filter(|l| l.is_straight()).collect();
 
-        let mut board = Board::from(board_size(&lines));
+        let mut board = Board::from(bounding_box(&lines));
         for line in lines.iter() {
             board.mark_line(line);
         }
@@ -272,9 +628,9 @@ mod tests {
     #[test]
     fn test_second_part() {
         let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
-        let lines: Vec<Line> = parse_vent_lines(input).into_iter().collect();
+        let lines: Vec<Line> = parse_vent_lines(input).unwrap().into_iter().collect();
 
-        let mut board = Board::from(board_size(&lines));
+        let mut board = Board::from(bounding_box(&lines));
         for line in lines.iter() {
             board.mark_line(line);
         }
@@ -282,6 +638,37 @@ mod tests {
         assert_eq!(board.overlapping_position_count(), 12);
     }
 
+    #[test]
+    fn test_overlap_queries() {
+        let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
+        let lines = parse_vent_lines(input).unwrap();
+
+        let mut board = Board::from(bounding_box(&lines));
+        for (idx, line) in lines.iter().enumerate() {
+            assert_eq!(board.mark_line(line), idx);
+        }
+
+        assert_eq!(board.count_at_least(1), 39);
+        assert_eq!(board.count_at_least(2), 12);
+        assert_eq!(board.count_at_least(3), 2);
+        assert_eq!(board.count_at_least(4), 0);
+        assert_eq!(board.histogram(), BTreeMap::from([(1, 27), (2, 10), (3, 2)]));
+
+        assert_eq!(board.max_overlap(), Some((3, vec![Point(4, 4), Point(6, 4)])));
+        assert_eq!(board.lines_through(&Point(4, 4)), vec![1, 2, 8]);
+        assert_eq!(board.lines_through(&Point(6, 4)), vec![2, 5, 9]);
+        assert_eq!(board.lines_through(&Point(7, 4)), vec![2, 4]);
+        assert_eq!(board.lines_through(&Point(9, 0)), Vec::<usize>::new());
+
+        // Lines with steeper slopes skip over points between their steps
+        let line = Line(Point(0, 0), Point(4, 2));
+        assert!(line.contains(&Point(2, 1)));
+        assert!(!line.contains(&Point(1, 0)));
+        assert!(!line.contains(&Point(6, 3)));
+
+        assert_eq!(Board::from((Point(0, 0), Point(0, 0))).max_overlap(), None);
+    }
+
     #[test]
     fn test_straight_lines() {
         let line = Line(Point(2, 7), Point(4, 7));
@@ -299,4 +686,67 @@ mod tests {
         assert!(!line.is_vertical());
         assert!(!line.is_straight());
     }
+
+    #[test]
+    fn test_storage_modes() {
+        let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
+        let lines = parse_vent_lines(input).unwrap();
+
+        let mut dense = Board::new(bounding_box(&lines), StorageMode::Dense);
+        let mut sparse = Board::new(bounding_box(&lines), StorageMode::Sparse);
+        for line in lines.iter() {
+            dense.mark_line(line);
+            sparse.mark_line(line);
+        }
+
+        assert!(!dense.is_sparse());
+        assert!(sparse.is_sparse());
+        assert_eq!(sparse.overlapping_position_count(), 12);
+        assert_eq!(sparse.to_string(), dense.to_string());
+
+        // Lines far apart from each other would need a trillion dense cells
+        let lines = vec![Line(Point(0, 0), Point(0, 1)), Line(Point(1_000_000, 999_990), Point(1_000_000, 1_000_000)), Line(Point(999_990, 1_000_000), Point(1_000_000, 1_000_000))];
+        let mut board = Board::from(bounding_box(&lines));
+        for line in lines.iter() {
+            board.mark_line(line);
+        }
+
+        assert!(board.is_sparse());
+        assert_eq!(board.overlapping_position_count(), 1);
+        assert_eq!(board.get(&Point(1_000_000, 1_000_000)), 2);
+    }
+
+    #[test]
+    fn test_signed_coordinates() {
+        let input: Vec<String> = "-3,-1 -> 2,-1\n0,-3 -> 0,1\n-2,1 -> 1,-2".lines().map(|e| e.to_string()).collect();
+        let lines = parse_vent_lines(input).unwrap();
+
+        let mut board = Board::from(bounding_box(&lines));
+        for line in lines.iter() {
+            board.mark_line(line);
+        }
+
+        assert_eq!(board.origin, Point(-3, -3));
+        assert_eq!((board.width, board.height), (6, 5));
+        assert_eq!(board.get(&Point(0, -1)), 3);
+        assert_eq!(board.overlapping_position_count(), 1);
+
+        let expected = concat!(
+            "   -3 0\n",
+            "-3 ...1..\n",
+            "-2 ...11.\n",
+            "-1 111311\n",
+            " 0 ..11..\n",
+            " 1 .1.1..\n",
+        );
+        assert_eq!(board.to_string(), expected);
+
+        // A board offset far from the origin only covers the lines themselves
+        let lines = vec![Line(Point(1_000_000, 5), Point(1_000_009, 5))];
+        let mut board = Board::from(bounding_box(&lines));
+        board.mark_line(&lines[0]);
+
+        assert!(!board.is_sparse());
+        assert_eq!(board.to_string(), "  1000000\n5 1111111111\n");
+    }
 }
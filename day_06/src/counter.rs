@@ -0,0 +1,196 @@
+//! The number types a pond can count fish with. Fixed width integers are fast but run out of
+//! room after a few hundred days, arbitrary precision integers never overflow but grow without
+//! bound, and modular integers keep a fixed size by only tracking the remainder.
+
+use num_bigint::BigUint;
+
+pub trait Counter: Clone + std::fmt::Debug + std::fmt::Display + PartialEq {
+    fn zero() -> Self;
+    fn one() -> Self;
+
+    /// Returns `None` when the result doesn't fit in the counter.
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+
+    /// Returns `None` when the result doesn't fit in the counter.
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::zero()
+    }
+
+    /// The base ten logarithm of the count, or `None` when the counter doesn't track the full
+    /// magnitude. Zero has a logarithm of negative infinity.
+    fn log10(&self) -> Option<f64>;
+}
+
+/// Raised when a population no longer fits in the counter it's being tracked with.
+#[derive(Debug, PartialEq)]
+pub struct Overflow {
+    pub counter: &'static str,
+}
+
+impl Overflow {
+    pub fn of<C: Counter>() -> Self {
+        Overflow { counter: std::any::type_name::<C>() }
+    }
+}
+
+impl std::fmt::Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "population no longer fits in a {}", self.counter)
+    }
+}
+
+impl Counter for u64 {
+    fn zero() -> Self {
+        0
+    }
+
+    fn one() -> Self {
+        1
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u64::checked_add(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        u64::checked_mul(*self, *other)
+    }
+
+    fn log10(&self) -> Option<f64> {
+        Some((*self as f64).log10())
+    }
+}
+
+impl Counter for u128 {
+    fn zero() -> Self {
+        0
+    }
+
+    fn one() -> Self {
+        1
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u128::checked_add(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        u128::checked_mul(*self, *other)
+    }
+
+    fn log10(&self) -> Option<f64> {
+        Some((*self as f64).log10())
+    }
+}
+
+impl Counter for BigUint {
+    fn zero() -> Self {
+        BigUint::default()
+    }
+
+    fn one() -> Self {
+        BigUint::from(1_u8)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+
+    /// Only the leading 64 bits matter for the logarithm, which keeps it cheap for numbers with
+    /// millions of digits that wouldn't fit in a float.
+    fn log10(&self) -> Option<f64> {
+        let shift = self.bits().saturating_sub(64);
+        let leading = (self >> shift).to_u64_digits().first().copied().unwrap_or(0);
+
+        Some((leading as f64).log10() + shift as f64 * std::f64::consts::LOG10_2)
+    }
+}
+
+/// An integer modulo `M`. A modulus of `10^k` keeps the last `k` digits of the population,
+/// while a large prime is handy for checking results against other implementations.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ModInt<const M: u64> {
+    value: u64,
+}
+
+impl<const M: u64> ModInt<M> {
+    pub fn new(value: u64) -> Self {
+        ModInt { value: value % M }
+    }
+
+    #[cfg(test)]
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+}
+
+impl<const M: u64> std::fmt::Display for ModInt<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl<const M: u64> Counter for ModInt<M> {
+    fn zero() -> Self {
+        ModInt::new(0)
+    }
+
+    fn one() -> Self {
+        ModInt::new(1)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(ModInt { value: ((self.value as u128 + other.value as u128) % M as u128) as u64 })
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(ModInt { value: ((self.value as u128 * other.value as u128) % M as u128) as u64 })
+    }
+
+    fn log10(&self) -> Option<f64> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_width_overflow() {
+        assert_eq!(Counter::checked_add(&u64::MAX, &0), Some(u64::MAX));
+        assert_eq!(Counter::checked_add(&u64::MAX, &1), None);
+        assert_eq!(Counter::checked_mul(&(1_u128 << 64), &(1_u128 << 64)), None);
+
+        assert_eq!(Overflow::of::<u64>().to_string(), "population no longer fits in a u64");
+    }
+
+    #[test]
+    fn test_modular_arithmetic() {
+        let a = ModInt::<1_000_000_007>::new(1_000_000_006);
+        let b = ModInt::<1_000_000_007>::new(5);
+
+        assert_eq!(a.checked_add(&b), Some(ModInt::new(4)));
+        assert_eq!(a.checked_mul(&b), Some(ModInt::new(1_000_000_002)));
+
+        // Products of the largest residues don't fit in a u64 before reduction
+        let c = ModInt::<{ u64::MAX }>::new(u64::MAX - 1);
+        assert_eq!(c.checked_mul(&c).map(|v| v.value()), Some(1));
+        assert!(ModInt::<7>::new(14).is_zero());
+        assert_eq!(ModInt::<7>::new(3).log10(), None);
+    }
+
+    #[test]
+    fn test_big_logarithm() {
+        assert!((BigUint::from(1000_u32).log10().unwrap() - 3.0).abs() < 1e-12);
+
+        let huge = BigUint::from(10_u8).pow(5000);
+        assert!((huge.log10().unwrap() - 5000.0).abs() < 1e-9);
+    }
+}
@@ -1,82 +1,528 @@
 #![allow(unused_variables)]
 
-use num_bigint::{BigUint, ToBigUint};
+mod counter;
+mod matrix;
+mod series;
+
+use num_bigint::BigUint;
 
 use common::read_puzzle_input;
 
-fn create_empty_fish_buckets() -> [BigUint; 9] {
-    [
-        0_usize.to_biguint().unwrap(),
-        0_usize.to_biguint().unwrap(),
-        0_usize.to_biguint().unwrap(),
-        0_usize.to_biguint().unwrap(),
-        0_usize.to_biguint().unwrap(),
-        0_usize.to_biguint().unwrap(),
-        0_usize.to_biguint().unwrap(),
-        0_usize.to_biguint().unwrap(),
-        0_usize.to_biguint().unwrap(),
-    ]
+use counter::{Counter, ModInt, Overflow};
+use matrix::Matrix;
+
+/// The pond the 9999999 day challenge starts from.
+const CHALLENGE_POND: &str = "3,4,3,1,2";
+
+/// How many days the pond is followed one at a time looking for a population before giving up.
+const DAYS_UNTIL_LIMIT: usize = 100_000;
+
+/// How a species of fish ages and breeds. After reaching a timer of zero a fish spawns a newborn
+/// and restarts at `reset_timer`. Newborns wait `maturation_delay` days before their timer of
+/// `newborn_timer` starts counting down. When `death_age` is set fish die once they've lived that
+/// many days, fish in the initial pond being counted as newborns at the start of the simulation.
+/// A fish reaching its death age on a spawning day still spawns.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Lifecycle {
+    reset_timer: usize,
+    newborn_timer: usize,
+    maturation_delay: usize,
+    death_age: Option<usize>,
 }
 
-#[derive(Debug)]
-struct Pond {
-    fish_buckets: [BigUint; 9]
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Lifecycle { reset_timer: 6, newborn_timer: 8, maturation_delay: 0, death_age: None }
+    }
 }
 
-impl Default for Pond {
+impl Lifecycle {
+    /// Newborns sit above their timer for the maturation delay, counting down through those
+    /// extra slots first.
+    fn timer_slots(&self) -> usize {
+        self.reset_timer.max(self.newborn_timer + self.maturation_delay) + 1
+    }
+
+    /// Ages only need tracking when fish can die. A death age of zero is treated as one.
+    fn age_slots(&self) -> usize {
+        self.death_age.map_or(1, |age| age.max(1))
+    }
+
+    fn bucket_count(&self) -> usize {
+        self.timer_slots() * self.age_slots()
+    }
+
+    fn bucket(&self, age: usize, timer: usize) -> usize {
+        age * self.timer_slots() + timer
+    }
+
+    /// Every move a day makes between buckets as `(from, to)` pairs. A spawning fish appears
+    /// twice, once for itself and once for its newborn.
+    fn transitions(&self) -> Vec<(usize, usize)> {
+        let mut transitions = vec![];
+
+        for age in 0..self.age_slots() {
+            let next_age = match self.death_age {
+                Some(_) if age + 1 == self.age_slots() => None,
+                Some(_) => Some(age + 1),
+                None => Some(age),
+            };
+
+            for timer in 0..self.timer_slots() {
+                let from = self.bucket(age, timer);
+
+                if timer == 0 {
+                    transitions.push((from, self.bucket(0, self.newborn_timer + self.maturation_delay)));
+                }
+
+                if let Some(next_age) = next_age {
+                    let next_timer = if timer == 0 { self.reset_timer } else { timer - 1 };
+                    transitions.push((from, self.bucket(next_age, next_timer)));
+                }
+            }
+        }
+
+        transitions
+    }
+
+    fn transition<C: Counter>(&self) -> Matrix<C> {
+        let mut matrix = Matrix::zero(self.bucket_count());
+        for (from, to) in self.transitions() {
+            matrix.increment(to, from);
+        }
+
+        matrix
+    }
+}
+
+/// Fish are counted with arbitrary precision by default, the pond can count with any other
+/// `Counter` instead. The total of every bucket always fits in the counter, advancing reports an
+/// overflow rather than producing a pond that can't be counted.
+#[derive(Clone, Debug)]
+struct Pond<C = BigUint> {
+    lifecycle: Lifecycle,
+    fish_buckets: Vec<C>,
+}
+
+impl<C: Counter> Default for Pond<C> {
     fn default() -> Self {
-        Pond { fish_buckets: create_empty_fish_buckets() }
+        Pond::new(Lifecycle::default())
     }
 }
 
-impl Pond {
-    fn advance(&mut self, count: usize) {
+impl<C: Counter> Pond<C> {
+    fn new(lifecycle: Lifecycle) -> Self {
+        Pond { lifecycle, fish_buckets: vec![C::zero(); lifecycle.bucket_count()] }
+    }
+
+    /// Adds a newly observed fish with the given timer.
+    fn add_fish(&mut self, timer: usize) {
+        assert!(timer < self.lifecycle.timer_slots(), "timer {} is longer than the lifecycle allows", timer);
+
+        let bucket = &mut self.fish_buckets[self.lifecycle.bucket(0, timer)];
+        *bucket = bucket.checked_add(&C::one()).unwrap();
+    }
+
+    /// Moves the pond forward by `count` days in a logarithmic number of steps by raising the
+    /// daily transition to the power of `count`. Each matrix product costs around the cube of
+    /// the bucket count while a single day only visits each bucket a couple of times, so spans
+    /// shorter than the square of the bucket count are simulated one day at a time instead,
+    /// which matters once a death age multiplies the buckets. The transition itself can also
+    /// outgrow a fixed width counter before the population does, in which case the days are
+    /// simulated one at a time too so an overflow is only reported once the population really
+    /// doesn't fit. The pond is left unchanged when that happens.
+    fn advance(&mut self, count: usize) -> Result<(), Overflow> {
+        if self.fish_buckets.iter().all(|bucket| bucket.is_zero()) {
+            return Ok(());
+        }
+
+        let bucket_count = self.lifecycle.bucket_count();
+        let fast_forwarded = match count > bucket_count.saturating_mul(bucket_count) {
+            true => self.lifecycle.transition().pow(count).and_then(|m| m.apply(&self.fish_buckets)).ok(),
+            false => None,
+        };
+
+        let buckets = match fast_forwarded {
+            Some(buckets) => buckets,
+            None => {
+                let mut pond = self.clone();
+                pond.advance_iterative(count)?;
+
+                *self = pond;
+                return Ok(());
+            }
+        };
+
+        total(&buckets)?;
+        self.fish_buckets = buckets;
+
+        Ok(())
+    }
+
+    /// Moves the pond forward one day at a time, stopping as soon as the population overflows.
+    fn advance_iterative(&mut self, count: usize) -> Result<(), Overflow> {
+        let transitions = self.lifecycle.transitions();
+
         for i in 0..count {
-            let mut new_fish_buckets = create_empty_fish_buckets();
-            for (ticks, count) in self.fish_buckets.iter().enumerate() {
-                if ticks == 0 {
-                    new_fish_buckets[6] += count;
-                    new_fish_buckets[8] += count;
-                } else {
-                    new_fish_buckets[ticks - 1] += count;
-                }
+            let mut new_fish_buckets = vec![C::zero(); self.fish_buckets.len()];
+            for (from, to) in transitions.iter() {
+                new_fish_buckets[*to] = new_fish_buckets[*to].checked_add(&self.fish_buckets[*from]).ok_or_else(Overflow::of::<C>)?;
             }
 
+            total(&new_fish_buckets)?;
             self.fish_buckets = new_fish_buckets;
         }
+
+        Ok(())
+    }
+
+    fn count(&self) -> C {
+        total(&self.fish_buckets).expect("pond totals always fit in their counter")
+    }
+
+    /// The number of fish with each timer, whatever their age.
+    fn timer_counts(&self) -> Vec<C> {
+        let slots = self.lifecycle.timer_slots();
+
+        (0..slots)
+            .map(|timer| total(&self.fish_buckets.iter().skip(timer).step_by(slots).cloned().collect::<Vec<C>>()))
+            .collect::<Result<Vec<C>, Overflow>>()
+            .expect("pond totals always fit in their counter")
+    }
+}
+
+/// When a pond first holds some number of fish.
+#[derive(Debug, PartialEq)]
+enum Arrival {
+    Reached(usize),
+    /// The pond dies out before getting there.
+    Never,
+    /// Not reached within this many days, though it still could be later.
+    GaveUp(usize),
+}
+
+impl<C: Counter + PartialOrd> Pond<C> {
+    /// The first day on which the pond holds at least `threshold` fish. Without a death age the
+    /// pond is pushed forward by doubling spans of days until it passes the threshold, then the
+    /// same squared transitions narrow down the exact day, so only a logarithmic number of
+    /// matrix products is needed however large the threshold is. This relies on the population
+    /// never shrinking.
+    ///
+    /// Lifecycles with a death age, and fixed width counters that can't hold the larger
+    /// transitions, are searched linearly one day at a time. Populations that die can hold
+    /// steady below the threshold forever, so that search gives up after `DAYS_UNTIL_LIMIT`
+    /// days.
+    fn days_until(&self, threshold: &C) -> Result<Arrival, Overflow> {
+        if self.count() >= *threshold {
+            return Ok(Arrival::Reached(0));
+        }
+
+        if self.lifecycle.death_age.is_some() {
+            return self.days_until_iterative(threshold, DAYS_UNTIL_LIMIT);
+        }
+
+        match self.days_until_by_doubling(threshold) {
+            Err(_) => self.days_until_iterative(threshold, DAYS_UNTIL_LIMIT),
+            result => result,
+        }
     }
 
-    fn count(&self) -> BigUint {
-        self.fish_buckets.iter().sum()
+    fn days_until_by_doubling(&self, threshold: &C) -> Result<Arrival, Overflow> {
+        // Each power advances the pond by twice as many days as the one before it
+        let mut powers = vec![self.lifecycle.transition::<C>()];
+
+        loop {
+            let power = powers.last().unwrap();
+            let reached = total(&power.apply(&self.fish_buckets)?)?;
+
+            if reached >= *threshold {
+                break;
+            }
+
+            if reached.is_zero() {
+                return Ok(Arrival::Never);
+            }
+
+            if powers.len() == usize::BITS as usize - 1 {
+                return Ok(Arrival::GaveUp(1 << (powers.len() - 1)));
+            }
+
+            let next = power.multiply(power)?;
+            powers.push(next);
+        }
+
+        // Take every span that keeps the pond below the threshold, the day after is the answer
+        let mut day = 0;
+        let mut buckets = self.fish_buckets.clone();
+
+        for (k, power) in powers.iter().enumerate().rev() {
+            let candidate = power.apply(&buckets)?;
+
+            if total(&candidate)? < *threshold {
+                buckets = candidate;
+                day += 1 << k;
+            }
+        }
+
+        Ok(Arrival::Reached(day + 1))
     }
+
+    fn days_until_iterative(&self, threshold: &C, limit: usize) -> Result<Arrival, Overflow> {
+        let mut pond = self.clone();
+        let mut day = 0;
+
+        while pond.count() < *threshold {
+            if pond.count().is_zero() {
+                return Ok(Arrival::Never);
+            }
+
+            if day == limit {
+                return Ok(Arrival::GaveUp(day));
+            }
+
+            pond.advance_iterative(1)?;
+            day += 1;
+        }
+
+        Ok(Arrival::Reached(day))
+    }
+}
+
+fn total<C: Counter>(buckets: &[C]) -> Result<C, Overflow> {
+    buckets.iter().try_fold(C::zero(), |total, bucket| total.checked_add(bucket).ok_or_else(Overflow::of::<C>))
 }
 
-impl From<String> for Pond {
-    fn from(start: String) -> Self {
-        let fish: Vec<usize> = start.trim().split(',').map(|i| i.parse::<usize>().unwrap()).collect();
+#[derive(Debug, PartialEq)]
+enum PondError {
+    InvalidTimer { field: usize, value: String },
+    TimerTooLong { field: usize, value: usize, max: usize },
+}
 
-        let mut pond = Pond::default();
-        for f in fish.iter() {
-            pond.fish_buckets[*f] += 1_usize.to_biguint().unwrap();
+impl std::fmt::Display for PondError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PondError::InvalidTimer { field, value } => write!(f, "field {}: '{}' is not a valid timer", field, value),
+            PondError::TimerTooLong { field, value, max } => write!(
+                f,
+                "field {}: timer {} is longer than the lifecycle's maximum of {}",
+                field, value, max
+            ),
         }
+    }
+}
 
-        pond
+impl<C: Counter> Pond<C> {
+    /// Reads a comma separated list of timers, fields being numbered from zero. Blank input is
+    /// an empty pond.
+    fn parse(start: &str, lifecycle: Lifecycle) -> Result<Self, PondError> {
+        let mut pond = Pond::<C>::new(lifecycle);
+        if start.trim().is_empty() {
+            return Ok(pond);
+        }
+
+        let max = lifecycle.timer_slots() - 1;
+
+        for (field, value) in start.trim().split(',').map(|v| v.trim()).enumerate() {
+            let timer = value.parse::<usize>().map_err(|_| PondError::InvalidTimer { field, value: value.to_string() })?;
+            if timer > max {
+                return Err(PondError::TimerTooLong { field, value: timer, max });
+            }
+
+            pond.add_fish(timer);
+        }
+
+        Ok(pond)
     }
 }
 
-fn main() {
-    let input = read_puzzle_input(6);
-    let mut pond = Pond::from(input[0].clone());
+impl<C: Counter> TryFrom<String> for Pond<C> {
+    type Error = PondError;
+
+    fn try_from(start: String) -> Result<Self, PondError> {
+        Pond::parse(&start, Lifecycle::default())
+    }
+}
 
-    pond.advance(80);
+/// Reads the pond for `main`, exiting with the problem when the input isn't valid.
+fn read_pond<C: Counter>(input: &str, lifecycle: Lifecycle) -> Pond<C> {
+    match Pond::parse(input, lifecycle) {
+        Ok(pond) => pond,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Solves both parts counting with `C`, and the 9999999 day challenge when asked for. When a
+/// series format is given the daily snapshots are printed in that format instead.
+fn solve<C: Counter>(input: &str, lifecycle: Lifecycle, challenge: bool, series: Option<(&str, usize)>) -> Result<(), Overflow> {
+    let mut pond = read_pond::<C>(input, lifecycle);
+
+    if let Some((format, days)) = series {
+        print_series(&pond, format, days);
+        return Ok(());
+    }
+
+    pond.advance(80)?;
     println!("first question:{}", pond.count());
 
-    pond.advance(176);
+    pond.advance(176)?;
     println!("second question:{}", pond.count());
 
-    //let mut pond = Pond::from("3,4,3,1,2".to_string());
-    //pond.advance(9999999);
-    //println!("9999999 challenge result: {}", pond.count());
+    if challenge {
+        let mut pond = match Pond::<C>::parse(CHALLENGE_POND, lifecycle) {
+            Ok(pond) => pond,
+            Err(err) => {
+                eprintln!("challenge pond: {}", err);
+                std::process::exit(1);
+            }
+        };
+        pond.advance(9999999)?;
+        println!("9999999 challenge result: {}", pond.count());
+    }
+
+    Ok(())
+}
+
+/// Prints the first day the pond reaches the threshold counting with `C`.
+fn print_days_until<C: Counter + PartialOrd + std::str::FromStr>(input: &str, lifecycle: Lifecycle, threshold: &str) -> Result<(), Overflow> {
+    let threshold: C = match threshold.parse() {
+        Ok(threshold) => threshold,
+        Err(_) => {
+            eprintln!("'{}' is not a valid population", threshold);
+            std::process::exit(1);
+        }
+    };
+
+    let pond = read_pond::<C>(input, lifecycle);
+
+    match pond.days_until(&threshold)? {
+        Arrival::Reached(day) => println!("the pond reaches {} fish on day {}", threshold, day),
+        Arrival::Never => println!("the pond never reaches {} fish", threshold),
+        Arrival::GaveUp(days) => println!("the pond doesn't reach {} fish within {} days", threshold, days),
+    }
+
+    Ok(())
+}
+
+fn print_series<C: Counter>(pond: &Pond<C>, format: &str, days: usize) {
+    let snapshots: Vec<series::Snapshot<C>> = pond.snapshots().take(days + 1).collect();
+    if snapshots.len() <= days {
+        eprintln!("the population stopped fitting in the counter after day {}", snapshots.len() - 1);
+    }
+
+    match format {
+        "csv" => print!("{}", series::to_csv(&snapshots)),
+        "json" => print!("{}", series::to_json(&snapshots)),
+        _ => {
+            match series::render_chart(&snapshots, 20) {
+                Some(chart) => print!("{}", chart),
+                None => eprintln!("the counter doesn't track magnitudes, pick another to chart with"),
+            }
+
+            if let Some(observed) = series::observed_growth(&snapshots) {
+                println!("observed daily growth: {:.6}", observed);
+            }
+            println!("growth constant: {:.6}", series::growth_constant(&pond.lifecycle));
+        }
+    }
+}
+
+fn parse_days(option: &str, value: Option<String>) -> usize {
+    match value.and_then(|v| v.parse().ok()) {
+        Some(days) => days,
+        None => {
+            eprintln!("{} expects a number of days", option);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let input_entries = read_puzzle_input(6);
+
+    // An empty file is an empty pond
+    let input = input_entries.first().map_or("", String::as_str);
+
+    // The challenge result has hundreds of thousands of digits and takes a while to compute with
+    // arbitrary precision, so it only runs when asked for. `--counter` picks what the fish are
+    // counted with: `u64`, `u128`, `biguint` (the default), `mod` for counts modulo 10^9 + 7, or
+    // `mod10^6`, `mod10^9`, `mod10^12` and `mod10^18` for the last 6, 9, 12 or 18 digits.
+    // The lifecycle can be changed with `--reset-timer`, `--newborn-timer`, `--maturation-delay`
+    // and `--death-age`, each followed by a number of days. `--series` followed by `csv`, `json`
+    // or `chart` prints the pond for every day up to `--days` (80 by default) instead, while
+    // `--days-until` followed by a population prints the first day the pond reaches it.
+    let mut challenge = false;
+    let mut days_until = None;
+    let mut series = None;
+    let mut days = 80;
+    let mut counter = "biguint".to_string();
+    let mut lifecycle = Lifecycle::default();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--challenge" => challenge = true,
+            "--counter" => counter = args.next().expect("missing counter type"),
+            "--series" => series = args.next(),
+            "--days" => days = parse_days(&arg, args.next()),
+            "--days-until" => days_until = Some(args.next().expect("missing population")),
+            "--reset-timer" => lifecycle.reset_timer = parse_days(&arg, args.next()),
+            "--newborn-timer" => lifecycle.newborn_timer = parse_days(&arg, args.next()),
+            "--maturation-delay" => lifecycle.maturation_delay = parse_days(&arg, args.next()),
+            "--death-age" => lifecycle.death_age = Some(parse_days(&arg, args.next())),
+            _ => (),
+        }
+    }
+
+    let series = match series.as_deref() {
+        Some(format @ ("csv" | "json" | "chart")) => Some((format, days)),
+        Some(format) => {
+            eprintln!("unknown series format '{}'", format);
+            std::process::exit(1);
+        }
+        None => None,
+    };
+
+    if let Some(threshold) = days_until {
+        let result = match counter.as_str() {
+            "u64" => print_days_until::<u64>(input, lifecycle, &threshold),
+            "u128" => print_days_until::<u128>(input, lifecycle, &threshold),
+            "biguint" => print_days_until::<BigUint>(input, lifecycle, &threshold),
+            _ => {
+                eprintln!("populations counted with '{}' can't be compared", counter);
+                std::process::exit(1);
+            }
+        };
+
+        if let Err(err) = result {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    let result = match counter.as_str() {
+        "u64" => solve::<u64>(input, lifecycle, challenge, series),
+        "u128" => solve::<u128>(input, lifecycle, challenge, series),
+        "biguint" => solve::<BigUint>(input, lifecycle, challenge, series),
+        "mod" => solve::<ModInt<1_000_000_007>>(input, lifecycle, challenge, series),
+        "mod10^6" => solve::<ModInt<1_000_000>>(input, lifecycle, challenge, series),
+        "mod10^9" => solve::<ModInt<1_000_000_000>>(input, lifecycle, challenge, series),
+        "mod10^12" => solve::<ModInt<1_000_000_000_000>>(input, lifecycle, challenge, series),
+        "mod10^18" => solve::<ModInt<1_000_000_000_000_000_000>>(input, lifecycle, challenge, series),
+        _ => {
+            eprintln!("unknown counter type '{}'", counter);
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
 }
 
 #[cfg(test)]
@@ -88,21 +534,249 @@ mod tests {
     #[test]
     fn test_first_part() {
         let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
-        let mut pond = Pond::from(input[0].clone());
+        let mut pond: Pond = Pond::try_from(input[0].clone()).unwrap();
 
-        pond.advance(18);
-        assert_eq!(pond.count(), 26_usize.to_biguint().unwrap());
+        pond.advance(18).unwrap();
+        assert_eq!(pond.count(), BigUint::from(26_u64));
 
-        pond.advance(62);
-        assert_eq!(pond.count(), 5934_usize.to_biguint().unwrap());
+        pond.advance(62).unwrap();
+        assert_eq!(pond.count(), BigUint::from(5934_u64));
     }
 
     #[test]
     fn test_second_part() {
         let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
-        let mut pond = Pond::from(input[0].clone());
+        let mut pond: Pond = Pond::try_from(input[0].clone()).unwrap();
+
+        pond.advance(256).unwrap();
+        assert_eq!(pond.count(), BigUint::from(26984457539_u64));
+    }
+
+    #[test]
+    fn test_fast_forward_matches_iterative() {
+        let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
+
+        for days in [0, 1, 7, 9, 18, 100, 256, 1000] {
+            let mut fast: Pond = Pond::try_from(input[0].clone()).unwrap();
+            let mut iterative: Pond = Pond::try_from(input[0].clone()).unwrap();
+
+            fast.advance(days).unwrap();
+            iterative.advance_iterative(days).unwrap();
+            assert_eq!(fast.fish_buckets, iterative.fish_buckets, "days: {}", days);
+        }
+
+        // Spans long enough for the matrix path with fish that die
+        let lifecycle = Lifecycle { reset_timer: 4, newborn_timer: 1, maturation_delay: 2, death_age: Some(7) };
+        let mut fast = Pond::<u64>::new(lifecycle);
+        fast.add_fish(3);
+        let mut iterative = fast.clone();
+
+        fast.advance(2000).unwrap();
+        iterative.advance_iterative(2000).unwrap();
+        assert_eq!(fast.fish_buckets, iterative.fish_buckets);
+
+        // Advancing in several steps lands on the same pond as one long step
+        let mut pond: Pond = Pond::try_from(input[0].clone()).unwrap();
+        pond.advance(18).unwrap();
+        pond.advance(62).unwrap();
+        pond.advance(176).unwrap();
+        assert_eq!(pond.count(), BigUint::from(26984457539_u64));
+    }
+
+    #[test]
+    fn test_counter_types() {
+        let mut pond = Pond::<u128>::try_from(REFERENCE_INPUT.to_string()).unwrap();
+        pond.advance(256).unwrap();
+        assert_eq!(pond.count(), 26984457539);
+
+        let mut big = Pond::<BigUint>::try_from(REFERENCE_INPUT.to_string()).unwrap();
+        let mut modular = Pond::<ModInt<1_000_000_007>>::try_from(REFERENCE_INPUT.to_string()).unwrap();
+        big.advance(5000).unwrap();
+        modular.advance(5000).unwrap();
+        assert_eq!(BigUint::from(modular.count().value()), big.count() % 1_000_000_007_u64);
+
+        // A power of ten keeps the last digits
+        let mut digits = Pond::<ModInt<1_000_000>>::try_from(REFERENCE_INPUT.to_string()).unwrap();
+        digits.advance(256).unwrap();
+        assert_eq!(digits.count().value(), 457539);
+
+        // Find the last day the population still fits in a u64 by counting with a wider type
+        let mut wide = Pond::<u128>::try_from(REFERENCE_INPUT.to_string()).unwrap();
+        let mut last_fit = 0;
+        while wide.count() <= u64::MAX as u128 {
+            wide.advance(1).unwrap();
+            last_fit += 1;
+        }
+        last_fit -= 1;
+
+        // A failed advance leaves the pond where it was
+        let mut pond = Pond::<u64>::try_from(REFERENCE_INPUT.to_string()).unwrap();
+        pond.advance(last_fit).unwrap();
+        let before = pond.fish_buckets.clone();
+        assert_eq!(pond.advance(1), Err(Overflow::of::<u64>()));
+        assert_eq!(pond.fish_buckets, before);
+
+        let mut pond = Pond::<u64>::try_from(REFERENCE_INPUT.to_string()).unwrap();
+        assert!(pond.advance(1_000_000_000_000).is_err());
+    }
+
+    /// Follows every fish individually as `(age, timer)`, newborns taking their own entry.
+    fn simulate_individually(lifecycle: &Lifecycle, timers: &[usize], days: usize) -> usize {
+        let mut fish: Vec<(usize, usize)> = timers.iter().map(|t| (0, *t)).collect();
+
+        for _ in 0..days {
+            let mut next = vec![];
+
+            for (age, timer) in fish {
+                if timer == 0 {
+                    next.push((0, lifecycle.newborn_timer + lifecycle.maturation_delay));
+                }
+
+                if lifecycle.death_age.is_some_and(|death| age + 1 >= death) {
+                    continue;
+                }
+
+                next.push((age + 1, if timer == 0 { lifecycle.reset_timer } else { timer - 1 }));
+            }
+
+            fish = next;
+        }
+
+        fish.len()
+    }
+
+    #[test]
+    fn test_lifecycles() {
+        let timers = [3, 4, 3, 1, 2];
+        let lifecycles = [
+            Lifecycle::default(),
+            Lifecycle { reset_timer: 4, newborn_timer: 4, maturation_delay: 3, death_age: None },
+            Lifecycle { reset_timer: 6, newborn_timer: 8, maturation_delay: 0, death_age: Some(20) },
+            Lifecycle { reset_timer: 4, newborn_timer: 1, maturation_delay: 2, death_age: Some(7) },
+            Lifecycle { reset_timer: 9, newborn_timer: 3, maturation_delay: 1, death_age: Some(1) },
+        ];
+
+        for lifecycle in lifecycles.iter() {
+            for days in [0, 1, 5, 18, 40] {
+                let mut pond = Pond::<u64>::new(*lifecycle);
+                for timer in timers {
+                    pond.add_fish(timer);
+                }
+
+                pond.advance(days).unwrap();
+                assert_eq!(pond.count() as usize, simulate_individually(lifecycle, &timers, days), "{:?} after {} days", lifecycle, days);
+            }
+        }
+
+        // Long lived fish take far more buckets than the matrix path can handle in reasonable
+        // time, short spans are simulated a day at a time
+        let lifecycle = Lifecycle { death_age: Some(150), ..Default::default() };
+        let mut pond = Pond::<u64>::new(lifecycle);
+        for timer in timers {
+            pond.add_fish(timer);
+        }
+        pond.advance(80).unwrap();
+        assert_eq!(pond.count() as usize, simulate_individually(&lifecycle, &timers, 80));
+
+        // Longer timers leave room for initial fish with timers past eight
+        let mut pond = Pond::<u64>::new(Lifecycle { reset_timer: 10, newborn_timer: 12, ..Default::default() });
+        pond.add_fish(12);
+        pond.advance(13).unwrap();
+        assert_eq!(pond.count(), 2);
+    }
+
+    #[test]
+    fn test_days_until() {
+        let pond = Pond::<u64>::try_from(REFERENCE_INPUT.to_string()).unwrap();
+        let totals: Vec<u64> = pond.snapshots().take(300).map(|s| s.total).collect();
+
+        for threshold in [0, 5, 6, 26, 27, 5934, 1_000_000, 26984457539] {
+            let expected = totals.iter().position(|total| *total >= threshold).unwrap();
+            assert_eq!(pond.days_until(&threshold), Ok(Arrival::Reached(expected)), "threshold {}", threshold);
+        }
+
+        assert_eq!(pond.days_until(&26), Ok(Arrival::Reached(18)));
+        assert_eq!(Pond::<u64>::default().days_until(&1), Ok(Arrival::Never));
+
+        // Near the top of a u64 the doubled transitions overflow before the pond itself does
+        let wide = Pond::<u128>::try_from(REFERENCE_INPUT.to_string()).unwrap();
+        assert_eq!(pond.days_until(&(u64::MAX / 2)), Ok(wide.days_until(&(u64::MAX as u128 / 2)).unwrap()));
+        assert_eq!(pond.days_until(&u64::MAX), Err(Overflow::of::<u64>()));
+
+        // Astronomical thresholds only take a handful of matrix products
+        let pond: Pond = Pond::try_from(REFERENCE_INPUT.to_string()).unwrap();
+        let threshold = BigUint::from(10_u8).pow(1000);
+        let day = match pond.days_until(&threshold) {
+            Ok(Arrival::Reached(day)) => day,
+            other => panic!("unexpected {:?}", other),
+        };
+
+        let mut before = pond.clone();
+        before.advance(day - 1).unwrap();
+        assert!(before.count() < threshold);
+
+        before.advance(1).unwrap();
+        assert!(before.count() >= threshold);
+    }
+
+    #[test]
+    fn test_days_until_with_deaths() {
+        // The population rises and falls, so the first day over a threshold can come well before
+        // a later dip below it
+        let lifecycle = Lifecycle { reset_timer: 2, newborn_timer: 2, maturation_delay: 0, death_age: Some(4) };
+        let pond = Pond::<u64>::parse("0", lifecycle).unwrap();
+        let totals: Vec<u64> = pond.snapshots().take(500).map(|s| s.total).collect();
+        assert_eq!(totals[..8], [1, 2, 2, 2, 3, 2, 2, 4]);
+
+        for threshold in [2, 3, 4] {
+            let expected = totals.iter().position(|total| *total >= threshold).unwrap();
+            assert_eq!(pond.days_until(&threshold), Ok(Arrival::Reached(expected)), "threshold {}", threshold);
+        }
+        assert_eq!(pond.days_until(&4), Ok(Arrival::Reached(7)));
+
+        // A growing pond that takes too long to follow day by day isn't reported as never
+        // getting there
+        let lifecycle = Lifecycle { death_age: Some(20), ..Default::default() };
+        let pond = Pond::<u128>::parse("3,4,3,1,2", lifecycle).unwrap();
+        let day = match pond.days_until(&1_000_000) {
+            Ok(Arrival::Reached(day)) => day,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(pond.snapshots().position(|s| s.total >= 1_000_000), Some(day));
+        assert_eq!(pond.days_until_iterative(&1_000_000, day - 1), Ok(Arrival::GaveUp(day - 1)));
+
+        // Fish that die before they ever spawn
+        let lifecycle = Lifecycle { death_age: Some(2), ..Default::default() };
+        let pond = Pond::<u64>::parse("3", lifecycle).unwrap();
+        assert_eq!(pond.days_until(&2), Ok(Arrival::Never));
+
+        // Each fish spawns its replacement and dies the same day, the pond never grows
+        let lifecycle = Lifecycle { reset_timer: 0, newborn_timer: 0, maturation_delay: 0, death_age: Some(1) };
+        let pond = Pond::<u64>::parse("0", lifecycle).unwrap();
+        assert_eq!(pond.days_until(&2), Ok(Arrival::GaveUp(DAYS_UNTIL_LIMIT)));
+    }
+
+    #[test]
+    fn test_pond_validation() {
+        assert_eq!(Pond::<u64>::try_from(" 3, 4 ,3,1,2\n".to_string()).unwrap().count(), 5);
+        assert_eq!(Pond::<u64>::try_from(String::new()).unwrap().count(), 0);
+        assert_eq!(Pond::<u64>::try_from("  \n".to_string()).unwrap().count(), 0);
+
+        assert_eq!(
+            Pond::<u64>::try_from("3,4,9,1".to_string()).unwrap_err(),
+            PondError::TimerTooLong { field: 2, value: 9, max: 8 }
+        );
+        assert_eq!(
+            Pond::<u64>::try_from("3,x".to_string()).unwrap_err(),
+            PondError::InvalidTimer { field: 1, value: "x".to_string() }
+        );
+        assert_eq!(
+            Pond::<u64>::try_from("3,4,".to_string()).unwrap_err().to_string(),
+            "field 2: '' is not a valid timer"
+        );
 
-        pond.advance(256);
-        assert_eq!(pond.count(), 26984457539_usize.to_biguint().unwrap());
+        // Longer lifecycles accept longer timers
+        let lifecycle = Lifecycle { newborn_timer: 8, maturation_delay: 3, ..Default::default() };
+        assert_eq!(Pond::<u64>::parse("11,0", lifecycle).unwrap().count(), 2);
     }
 }
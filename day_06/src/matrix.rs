@@ -0,0 +1,85 @@
+//! Square matrices of population counts. Each day in the pond is a linear map from the old
+//! buckets to the new ones, so advancing by many days is the same as multiplying by a power of
+//! that map, which squaring computes in a logarithmic number of matrix products.
+
+use crate::counter::{Counter, Overflow};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Matrix<C> {
+    rows: Vec<Vec<C>>,
+}
+
+impl<C: Counter> Matrix<C> {
+    pub fn zero(size: usize) -> Self {
+        Matrix { rows: vec![vec![C::zero(); size]; size] }
+    }
+
+    pub fn identity(size: usize) -> Self {
+        let mut matrix = Matrix::zero(size);
+        for idx in 0..size {
+            matrix.rows[idx][idx] = C::one();
+        }
+
+        matrix
+    }
+
+    pub fn size(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Adds one to the entry mapping bucket `from` onto bucket `to`.
+    pub fn increment(&mut self, to: usize, from: usize) {
+        let entry = &mut self.rows[to][from];
+        *entry = entry.checked_add(&C::one()).expect("transition entries are tiny");
+    }
+
+    pub fn multiply(&self, other: &Matrix<C>) -> Result<Matrix<C>, Overflow> {
+        let size = self.size();
+        let mut product = Matrix::<C>::zero(size);
+
+        for (row, product_row) in self.rows.iter().zip(product.rows.iter_mut()) {
+            for (inner, value) in row.iter().enumerate() {
+                // Transition matrices are mostly zeroes, skipping them saves most of the work
+                if value.is_zero() {
+                    continue;
+                }
+
+                for (entry, other_value) in product_row.iter_mut().zip(other.rows[inner].iter()) {
+                    *entry = value.checked_mul(other_value)
+                        .and_then(|term| entry.checked_add(&term))
+                        .ok_or_else(Overflow::of::<C>)?;
+                }
+            }
+        }
+
+        Ok(product)
+    }
+
+    pub fn pow(&self, mut exponent: usize) -> Result<Matrix<C>, Overflow> {
+        let mut result = Matrix::identity(self.size());
+        let mut base = self.clone();
+
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.multiply(&base)?;
+            }
+
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base.multiply(&base)?;
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn apply(&self, vector: &[C]) -> Result<Vec<C>, Overflow> {
+        self.rows.iter()
+            .map(|row| {
+                row.iter().zip(vector.iter()).try_fold(C::zero(), |total, (a, b)| {
+                    a.checked_mul(b).and_then(|term| total.checked_add(&term)).ok_or_else(Overflow::of::<C>)
+                })
+            })
+            .collect()
+    }
+}
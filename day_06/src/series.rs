@@ -0,0 +1,236 @@
+//! Follows a pond day by day. Every snapshot records the fish per timer, with fish of different
+//! ages but the same timer counted together, and the snapshots can be written out as CSV or JSON
+//! or charted on a log scale.
+//!
+//! Over time the population of any lifecycle grows by a fixed factor each day, the largest
+//! eigenvalue of its daily transition. For the default lifecycle that's the real root of
+//! `x^9 = x^2 + 1`, around 1.091.
+
+use std::fmt::Write;
+
+use crate::counter::Counter;
+use crate::{Lifecycle, Pond};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot<C> {
+    pub day: usize,
+    pub buckets: Vec<C>,
+    pub total: C,
+}
+
+/// Yields the pond as it starts and then after every day, ending early if the population stops
+/// fitting in the counter.
+pub struct Snapshots<C> {
+    pond: Pond<C>,
+    day: usize,
+    finished: bool,
+}
+
+impl<C: Counter> Iterator for Snapshots<C> {
+    type Item = Snapshot<C>;
+
+    fn next(&mut self) -> Option<Snapshot<C>> {
+        if self.finished {
+            return None;
+        }
+
+        let snapshot = Snapshot { day: self.day, buckets: self.pond.timer_counts(), total: self.pond.count() };
+
+        self.finished = self.pond.advance_iterative(1).is_err();
+        self.day += 1;
+
+        Some(snapshot)
+    }
+}
+
+impl<C: Counter> Pond<C> {
+    pub fn snapshots(&self) -> Snapshots<C> {
+        Snapshots { pond: self.clone(), day: 0, finished: false }
+    }
+}
+
+pub fn to_csv<C: Counter>(snapshots: &[Snapshot<C>]) -> String {
+    let timers = snapshots.first().map_or(0, |s| s.buckets.len());
+
+    let mut header = vec!["day".to_string()];
+    header.extend((0..timers).map(|timer| format!("timer_{}", timer)));
+    header.push("total".to_string());
+
+    let mut output = header.join(",");
+    output.push('\n');
+
+    for snapshot in snapshots.iter() {
+        let buckets: Vec<String> = snapshot.buckets.iter().map(|b| b.to_string()).collect();
+        writeln!(output, "{},{},{}", snapshot.day, buckets.join(","), snapshot.total).unwrap();
+    }
+
+    output
+}
+
+/// Counts are written as plain JSON numbers however large they get.
+pub fn to_json<C: Counter>(snapshots: &[Snapshot<C>]) -> String {
+    let entries: Vec<String> = snapshots.iter()
+        .map(|snapshot| {
+            let buckets: Vec<String> = snapshot.buckets.iter().map(|b| b.to_string()).collect();
+            format!("  {{\"day\": {}, \"buckets\": [{}], \"total\": {}}}", snapshot.day, buckets.join(", "), snapshot.total)
+        })
+        .collect();
+
+    match entries.is_empty() {
+        true => "[]\n".to_string(),
+        false => format!("[\n{}\n]\n", entries.join(",\n")),
+    }
+}
+
+/// Draws the total population on a log scale, one column per snapshot and `height` rows from
+/// the smallest to the largest magnitude. Returns `None` when the counter can't provide the
+/// magnitudes, as with modular counts.
+pub fn render_chart<C: Counter>(snapshots: &[Snapshot<C>], height: usize) -> Option<String> {
+    let magnitudes: Vec<f64> = snapshots.iter()
+        .map(|s| s.total.log10().map(|m| m.max(0.0)))
+        .collect::<Option<Vec<f64>>>()?;
+
+    let low = magnitudes.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = magnitudes.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let span = (high - low).max(f64::EPSILON);
+
+    let rows: Vec<usize> = magnitudes.iter()
+        .map(|m| ((m - low) / span * (height - 1) as f64).round() as usize)
+        .collect();
+
+    let mut output = String::new();
+    for row in (0..height).rev() {
+        let label = low + span * row as f64 / (height - 1).max(1) as f64;
+        let line: String = rows.iter().map(|r| if *r == row { '*' } else if *r > row { '|' } else { ' ' }).collect();
+
+        writeln!(output, "1e{:<6.2}|{}", label, line.trim_end()).unwrap();
+    }
+
+    writeln!(output, "        +{}", "-".repeat(rows.len())).unwrap();
+    if let (Some(first), Some(last)) = (snapshots.first(), snapshots.last()) {
+        writeln!(output, "         day {} to {}", first.day, last.day).unwrap();
+    }
+
+    Some(output)
+}
+
+/// The average daily growth over the snapshots, the `n`th root of how much the population grew
+/// in `n` days.
+pub fn observed_growth<C: Counter>(snapshots: &[Snapshot<C>]) -> Option<f64> {
+    let (first, last) = (snapshots.first()?, snapshots.last()?);
+    if last.day == first.day {
+        return None;
+    }
+
+    let change = last.total.log10()? - first.total.log10()?;
+    Some(10_f64.powf(change / (last.day - first.day) as f64))
+}
+
+/// Approximates the daily growth factor the lifecycle settles into by following the share of
+/// fish in each bucket until it stops changing. Populations that die out have a growth of zero.
+pub fn growth_constant(lifecycle: &Lifecycle) -> f64 {
+    const WARM_UP: usize = 2000;
+    const MEASURED: usize = 2000;
+
+    let transitions = lifecycle.transitions();
+    let mut buckets = vec![1.0; lifecycle.bucket_count()];
+    let mut log_growth = 0.0;
+
+    for day in 0..(WARM_UP + MEASURED) {
+        let mut next = vec![0.0; buckets.len()];
+        for (from, to) in transitions.iter() {
+            next[*to] += buckets[*from];
+        }
+
+        let total: f64 = next.iter().sum();
+        if total == 0.0 {
+            return 0.0;
+        }
+
+        // Periodic lifecycles never settle on a single ratio, averaging over many days evens
+        // that out
+        if day >= WARM_UP {
+            log_growth += total.ln();
+        }
+
+        buckets = next.iter().map(|b| b / total).collect();
+    }
+
+    (log_growth / MEASURED as f64).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference_pond() -> Pond<u64> {
+        let mut pond = Pond::default();
+        for timer in [3, 4, 3, 1, 2] {
+            pond.add_fish(timer);
+        }
+
+        pond
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let snapshots: Vec<Snapshot<u64>> = reference_pond().snapshots().take(19).collect();
+
+        assert_eq!(snapshots[0], Snapshot { day: 0, buckets: vec![0, 1, 1, 2, 1, 0, 0, 0, 0], total: 5 });
+        assert_eq!(snapshots[1].buckets, vec![1, 1, 2, 1, 0, 0, 0, 0, 0]);
+        assert_eq!(snapshots[18].total, 26);
+
+        // Snapshots stop once the next day no longer fits
+        let snapshots: Vec<Snapshot<u64>> = reference_pond().snapshots().collect();
+        assert!(snapshots.len() > 256 && snapshots.len() < 1000);
+    }
+
+    #[test]
+    fn test_exports() {
+        let snapshots: Vec<Snapshot<u64>> = reference_pond().snapshots().take(2).collect();
+
+        assert_eq!(
+            to_csv(&snapshots),
+            "day,timer_0,timer_1,timer_2,timer_3,timer_4,timer_5,timer_6,timer_7,timer_8,total\n\
+             0,0,1,1,2,1,0,0,0,0,5\n\
+             1,1,1,2,1,0,0,0,0,0,5\n"
+        );
+        assert_eq!(
+            to_json(&snapshots),
+            "[\n  {\"day\": 0, \"buckets\": [0, 1, 1, 2, 1, 0, 0, 0, 0], \"total\": 5},\n  \
+             {\"day\": 1, \"buckets\": [1, 1, 2, 1, 0, 0, 0, 0, 0], \"total\": 5}\n]\n"
+        );
+        assert_eq!(to_json::<u64>(&[]), "[]\n");
+    }
+
+    #[test]
+    fn test_chart() {
+        let snapshots: Vec<Snapshot<u64>> = reference_pond().snapshots().take(81).collect();
+        let chart = render_chart(&snapshots, 10).unwrap();
+        let lines: Vec<&str> = chart.lines().collect();
+
+        assert_eq!(lines.len(), 12);
+        assert!(lines[0].starts_with("1e3.77"));
+        assert!(lines[0].ends_with('*'));
+        assert!(lines[9].starts_with("1e0.70  |*"));
+
+        let modular: Vec<Snapshot<crate::ModInt<7>>> = Pond::default().snapshots().take(3).collect();
+        assert_eq!(render_chart(&modular, 10), None);
+    }
+
+    #[test]
+    fn test_growth_constant() {
+        let constant = growth_constant(&Lifecycle::default());
+
+        // The real root of x^9 - x^2 - 1
+        assert!((constant.powi(9) - constant.powi(2) - 1.0).abs() < 1e-9);
+        assert!((constant - 1.0910).abs() < 1e-4);
+
+        let snapshots: Vec<Snapshot<u64>> = reference_pond().snapshots().skip(100).take(300).collect();
+        assert!((observed_growth(&snapshots).unwrap() - constant).abs() < 1e-3);
+
+        // Fish that die before ever spawning can't sustain the pond
+        let lifecycle = Lifecycle { death_age: Some(3), ..Default::default() };
+        assert_eq!(growth_constant(&lifecycle), 0.0);
+    }
+}
@@ -0,0 +1,313 @@
+//! Ways of pricing a crab's move. A cost model gives the fuel for a single crab to move some
+//! distance, and can weigh crabs differently from one another. When the cost grows at a steady
+//! or increasing rate with the distance the total fuel for a target is convex, so the best
+//! target can be found by searching for where the total stops falling.
+
+/// Costs that can't say whether they're convex are measured, but only up to this distance.
+/// Beyond it they're treated as not convex.
+const MEASURED_DISTANCE_LIMIT: u64 = 1_000_000;
+
+/// Costs are expected never to fall as the distance grows.
+pub trait FuelCost {
+    /// The fuel used by a single crab moving `distance` positions.
+    fn cost(&self, distance: u64) -> u128;
+
+    /// How many times the crab at `crab` in the input counts towards the total.
+    fn weight(&self, crab: usize) -> u128 {
+        1
+    }
+
+    /// Whether the cost is known to be convex, `None` leaves it to be measured.
+    fn convex(&self) -> Option<bool> {
+        None
+    }
+
+    /// The distances where a cost that's otherwise linear changes slope. The total fuel of such
+    /// a cost only changes slope at a crab's position offset by one of these, so the best target
+    /// is always among them.
+    fn breakpoints(&self) -> Option<Vec<u64>> {
+        None
+    }
+
+    /// Distances splitting the cost into stretches that are each convex. The total fuel is then
+    /// convex between any two neighbouring targets found by offsetting a crab's position by one
+    /// of these, so each stretch can be searched on its own.
+    fn convex_pieces(&self) -> Option<Vec<u64>> {
+        None
+    }
+
+    /// For costs of the form `(a * d^2 + b * d) / divisor`, the `(a, b, divisor)` describing
+    /// them. Totals of those only depend on the sums of distances and squared distances, which
+    /// can be updated from one target to the next without visiting every crab.
+    fn polynomial(&self) -> Option<(u128, u128, u128)> {
+        None
+    }
+}
+
+/// Lets a model picked at runtime stand in wherever a cost is expected.
+impl<C: FuelCost + ?Sized> FuelCost for Box<C> {
+    fn cost(&self, distance: u64) -> u128 {
+        (**self).cost(distance)
+    }
+
+    fn weight(&self, crab: usize) -> u128 {
+        (**self).weight(crab)
+    }
+
+    fn convex(&self) -> Option<bool> {
+        (**self).convex()
+    }
+
+    fn breakpoints(&self) -> Option<Vec<u64>> {
+        (**self).breakpoints()
+    }
+
+    fn convex_pieces(&self) -> Option<Vec<u64>> {
+        (**self).convex_pieces()
+    }
+
+    fn polynomial(&self) -> Option<(u128, u128, u128)> {
+        (**self).polynomial()
+    }
+}
+
+/// Every step costs one unit of fuel.
+pub struct Linear;
+
+impl FuelCost for Linear {
+    fn cost(&self, distance: u64) -> u128 {
+        distance as u128
+    }
+
+    fn convex(&self) -> Option<bool> {
+        Some(true)
+    }
+
+    fn breakpoints(&self) -> Option<Vec<u64>> {
+        Some(vec![0])
+    }
+
+    fn polynomial(&self) -> Option<(u128, u128, u128)> {
+        Some((0, 1, 1))
+    }
+}
+
+/// Each step costs one more than the step before it.
+pub struct Triangular;
+
+impl FuelCost for Triangular {
+    fn cost(&self, distance: u64) -> u128 {
+        distance as u128 * (distance as u128 + 1) / 2
+    }
+
+    fn convex(&self) -> Option<bool> {
+        Some(true)
+    }
+
+    fn polynomial(&self) -> Option<(u128, u128, u128)> {
+        Some((1, 1, 2))
+    }
+}
+
+pub struct Quadratic;
+
+impl FuelCost for Quadratic {
+    fn cost(&self, distance: u64) -> u128 {
+        distance as u128 * distance as u128
+    }
+
+    fn convex(&self) -> Option<bool> {
+        Some(true)
+    }
+
+    fn polynomial(&self) -> Option<(u128, u128, u128)> {
+        Some((1, 0, 1))
+    }
+}
+
+/// No crab uses more than `cap` fuel however far it moves.
+pub struct Capped<C> {
+    pub cost: C,
+    pub cap: u128,
+}
+
+impl<C: FuelCost> Capped<C> {
+    /// The first distance at which the cap applies.
+    fn capped_from(&self) -> u64 {
+        let (mut low, mut high) = (0, 1 << 62);
+
+        while low < high {
+            let middle = low + (high - low) / 2;
+
+            match self.cost.cost(middle) < self.cap {
+                true => low = middle + 1,
+                false => high = middle,
+            }
+        }
+
+        low
+    }
+}
+
+impl<C: FuelCost> FuelCost for Capped<C> {
+    fn cost(&self, distance: u64) -> u128 {
+        self.cost.cost(distance).min(self.cap)
+    }
+
+    fn weight(&self, crab: usize) -> u128 {
+        self.cost.weight(crab)
+    }
+
+    /// The slope changes where the cap starts, which can fall between two distances.
+    fn breakpoints(&self) -> Option<Vec<u64>> {
+        let capped_from = self.capped_from();
+
+        let mut breakpoints = self.cost.breakpoints()?;
+        breakpoints.retain(|distance| *distance < capped_from);
+        breakpoints.extend([capped_from.saturating_sub(1), capped_from]);
+
+        Some(breakpoints)
+    }
+
+    /// A convex cost stays convex up to the cap, and the cost is flat from there on.
+    fn convex_pieces(&self) -> Option<Vec<u64>> {
+        if self.cost.convex() != Some(true) {
+            return None;
+        }
+
+        let capped_from = self.capped_from();
+        Some(vec![capped_from.saturating_sub(1), capped_from])
+    }
+}
+
+/// Scales each crab's fuel by its weight, given in input order. Crabs past the end of the
+/// weights keep a weight of one.
+pub struct Weighted<C> {
+    pub cost: C,
+    pub weights: Vec<u64>,
+}
+
+impl<C: FuelCost> FuelCost for Weighted<C> {
+    fn cost(&self, distance: u64) -> u128 {
+        self.cost.cost(distance)
+    }
+
+    fn weight(&self, crab: usize) -> u128 {
+        self.weights.get(crab).map_or(1, |w| *w as u128) * self.cost.weight(crab)
+    }
+
+    fn convex(&self) -> Option<bool> {
+        self.cost.convex()
+    }
+
+    fn breakpoints(&self) -> Option<Vec<u64>> {
+        self.cost.breakpoints()
+    }
+
+    fn convex_pieces(&self) -> Option<Vec<u64>> {
+        self.cost.convex_pieces()
+    }
+
+    fn polynomial(&self) -> Option<(u128, u128, u128)> {
+        self.cost.polynomial()
+    }
+}
+
+/// Any function of the distance.
+pub struct Custom<F>(pub F);
+
+impl<F: Fn(u64) -> u128> FuelCost for Custom<F> {
+    fn cost(&self, distance: u64) -> u128 {
+        (self.0)(distance)
+    }
+}
+
+/// Whether moving the same distance in either direction gives a convex cost over distances up
+/// to `max_distance`. The increase from one distance to the next can't shrink, and moving at all
+/// can't be cheaper than staying put.
+pub fn is_convex(cost: &impl FuelCost, max_distance: u64) -> bool {
+    if let Some(convex) = cost.convex() {
+        return convex;
+    }
+
+    if max_distance == 0 {
+        return true;
+    }
+
+    if max_distance > MEASURED_DISTANCE_LIMIT {
+        return false;
+    }
+
+    let steps: Vec<i128> = (0..max_distance)
+        .map(|distance| cost.cost(distance + 1) as i128 - cost.cost(distance) as i128)
+        .collect();
+
+    steps[0] >= 0 && steps.windows(2).all(|pair| pair[0] <= pair[1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost_models() {
+        assert_eq!((0..5).map(|d| Linear.cost(d)).collect::<Vec<u128>>(), vec![0, 1, 2, 3, 4]);
+        assert_eq!((0..5).map(|d| Triangular.cost(d)).collect::<Vec<u128>>(), vec![0, 1, 3, 6, 10]);
+        assert_eq!((0..5).map(|d| Quadratic.cost(d)).collect::<Vec<u128>>(), vec![0, 1, 4, 9, 16]);
+
+        let capped = Capped { cost: Triangular, cap: 5 };
+        assert_eq!((0..5).map(|d| capped.cost(d)).collect::<Vec<u128>>(), vec![0, 1, 3, 5, 5]);
+
+        let weighted = Weighted { cost: Linear, weights: vec![3, 0] };
+        assert_eq!((0..3).map(|c| weighted.weight(c)).collect::<Vec<u128>>(), vec![3, 0, 1]);
+        assert_eq!(Custom(|d| d as u128 + 10).cost(2), 12);
+
+        // Distances across the whole signed range still fit
+        assert_eq!(Triangular.cost(u64::MAX), u64::MAX as u128 * (u64::MAX as u128 + 1) / 2);
+    }
+
+    #[test]
+    fn test_convexity() {
+        assert!(is_convex(&Linear, 100));
+        assert!(is_convex(&Triangular, 100));
+        assert!(is_convex(&Quadratic, 100));
+        assert!(is_convex(&Weighted { cost: Quadratic, weights: vec![5] }, 100));
+
+        // A cap only matters once it's reached within the distances being measured
+        assert!(is_convex(&Capped { cost: Linear, cap: 50 }, 50));
+        assert!(!is_convex(&Capped { cost: Linear, cap: 50 }, 51));
+
+        assert!(!is_convex(&Custom(|d: u64| (d as f64).sqrt() as u128), 10));
+        assert!(!is_convex(&Custom(|d: u64| 10 - d.min(10) as u128), 10));
+        assert!(!is_convex(&Custom(|d: u64| d as u128 * 7 + 3 * (d > 0) as u128), 10));
+        assert!(is_convex(&Custom(|d: u64| (d as u128).pow(3)), 10));
+
+        // Too far to measure, and nothing says otherwise
+        assert!(!is_convex(&Custom(|d: u64| d as u128), 1 << 40));
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        assert_eq!(Capped { cost: Linear, cap: 5 }.breakpoints(), Some(vec![0, 4, 5]));
+        assert_eq!(Capped { cost: Custom(|d: u64| d as u128 * 3), cap: 10 }.breakpoints(), None);
+        assert_eq!(Capped { cost: Weighted { cost: Linear, weights: vec![] }, cap: 0 }.breakpoints(), Some(vec![0, 0]));
+        assert_eq!(Capped { cost: Triangular, cap: 5 }.breakpoints(), None);
+
+        assert_eq!(Capped { cost: Triangular, cap: 100 }.convex_pieces(), Some(vec![13, 14]));
+        assert_eq!(Capped { cost: Custom(|d: u64| d as u128), cap: 10 }.convex_pieces(), None);
+    }
+
+    #[test]
+    fn test_polynomials() {
+        let costs: Vec<Box<dyn FuelCost>> = vec![Box::new(Linear), Box::new(Triangular), Box::new(Quadratic)];
+
+        for cost in costs.iter() {
+            let (a, b, divisor) = cost.polynomial().unwrap();
+            for distance in 0..20 {
+                assert_eq!(cost.cost(distance), (a * (distance as u128).pow(2) + b * distance as u128) / divisor);
+            }
+        }
+
+        assert_eq!(Capped { cost: Linear, cap: 5 }.polynomial(), None);
+    }
+}
@@ -1,138 +1,443 @@
 #![allow(unused_variables)]
 
+mod cost;
+mod profile;
+
 use common::read_puzzle_input;
 
+use cost::FuelCost;
+
+/// Costs with no structure to exploit are only minimized by checking every target, which is only
+/// done for crabs spanning at most this many positions.
+const SCANNED_SPAN_LIMIT: u64 = 1_000_000;
+
+/// The most targets `--profile` covers. Crabs spread further apart are profiled around the best
+/// target.
+const PROFILED_TARGET_LIMIT: u64 = 100_000;
+
+/// Crabs are kept both in input order, for cost models that treat crabs differently, and as the
+/// distinct positions they occupy in increasing order along with the number of crabs at each.
+/// Only occupied positions take up any room, so crabs can be spread across the whole signed
+/// range.
 struct ShipLine {
-    positions: Vec<usize>,
-    width: usize,
+    crabs: Vec<i64>,
+    positions: Vec<(i64, u64)>,
 }
 
-fn factorial_sum(count: usize) -> usize {
-    let mut sum = 0;
+#[derive(Debug, PartialEq)]
+enum ShipLineError {
+    Empty,
+    InvalidPosition { field: usize, value: String },
+    TooWideToScan { span: u64 },
+    FuelOverflow { target: i64 },
+}
 
-    for n in 0..=count {
-        sum += n;
+impl std::fmt::Display for ShipLineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShipLineError::Empty => write!(f, "there are no crabs to align"),
+            ShipLineError::InvalidPosition { field, value } => write!(f, "field {}: '{}' is not a valid position", field, value),
+            ShipLineError::TooWideToScan { span } => write!(
+                f,
+                "the crabs span {} positions, too many to check one by one under this cost model",
+                span
+            ),
+            ShipLineError::FuelOverflow { target } => write!(f, "moving every crab to {} takes more fuel than can be counted", target),
+        }
     }
+}
 
-    sum
+#[derive(Debug, PartialEq)]
+enum CostModelError {
+    Unknown { model: String },
+    InvalidCap { value: String },
+    InvalidExponent { value: String },
+    UnreadableWeights { path: String, reason: String },
+    InvalidWeight { field: usize, value: String },
 }
 
-impl ShipLine {
-    fn factorial_fuel_to_position(&self, target_position: usize) -> usize {
-        if target_position >= self.width {
-            panic!("attempt to move ships into position beyond the end");
+impl std::fmt::Display for CostModelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CostModelError::Unknown { model } => write!(f, "unknown cost model '{}'", model),
+            CostModelError::InvalidCap { value } => write!(f, "'{}' is not a valid fuel cap", value),
+            CostModelError::InvalidExponent { value } => write!(f, "'{}' is not a valid exponent", value),
+            CostModelError::UnreadableWeights { path, reason } => write!(f, "unable to read weights from {}: {}", path, reason),
+            CostModelError::InvalidWeight { field, value } => write!(f, "field {}: '{}' is not a valid weight", field, value),
         }
+    }
+}
 
-        let mut total_fuel = 0;
+/// Reads a cost model named on the command line: `linear`, `triangular`, `quadratic`,
+/// `capped:<fuel>`, which caps the triangular cost, `power:<exponent>`, which raises the distance
+/// to a power, or `weighted:<file>`, which scales the triangular cost of each crab by the
+/// comma-separated weights in the file.
+fn parse_cost_model(model: &str) -> Result<Box<dyn FuelCost>, CostModelError> {
+    match model.split_once(':') {
+        None if model == "linear" => Ok(Box::new(cost::Linear)),
+        None if model == "triangular" => Ok(Box::new(cost::Triangular)),
+        None if model == "quadratic" => Ok(Box::new(cost::Quadratic)),
+        Some(("capped", cap)) => match cap.parse() {
+            Ok(cap) => Ok(Box::new(cost::Capped { cost: cost::Triangular, cap })),
+            Err(_) => Err(CostModelError::InvalidCap { value: cap.to_string() }),
+        },
+        Some(("power", exponent)) => match exponent.parse::<u32>() {
+            Ok(exponent) => Ok(Box::new(cost::Custom(move |distance| (distance as u128).saturating_pow(exponent)))),
+            Err(_) => Err(CostModelError::InvalidExponent { value: exponent.to_string() }),
+        },
+        Some(("weighted", path)) => {
+            let weights = std::fs::read_to_string(path)
+                .map_err(|err| CostModelError::UnreadableWeights { path: path.to_string(), reason: err.to_string() })?;
+
+            Ok(Box::new(cost::Weighted { cost: cost::Triangular, weights: parse_weights(weights.trim())? }))
+        }
+        _ => Err(CostModelError::Unknown { model: model.to_string() }),
+    }
+}
 
-        for (existing_position, count) in self.positions.iter().enumerate() {
-            let distance = (target_position as isize - existing_position as isize).abs() as usize;
+fn parse_weights(line: &str) -> Result<Vec<u64>, CostModelError> {
+    line.split(',')
+        .enumerate()
+        .map(|(field, value)| value.trim().parse().map_err(|_| CostModelError::InvalidWeight { field, value: value.to_string() }))
+        .collect()
+}
 
-            if distance == 0 {
-                continue;
-            }
+/// The sum of every number from one up to `count`, a triangular number.
+fn factorial_sum(count: u64) -> u128 {
+    count as u128 * (count as u128 + 1) / 2
+}
 
-            let fuel_cost = factorial_sum(distance);
-            total_fuel += fuel_cost * count;
-        }
+impl ShipLine {
+    /// The first and last occupied positions. Targets outside them only move crabs further.
+    fn span(&self) -> (i64, i64) {
+        (self.positions[0].0, self.positions[self.positions.len() - 1].0)
+    }
 
-        total_fuel
+    fn crab_count(&self) -> u64 {
+        self.positions.iter().map(|(_, count)| count).sum()
     }
 
-    fn minimum_factorial_fuel_usage(&self) -> (usize, usize) {
-        let mut min_fuel: Option<usize> = None;
-        let mut target_position: usize = 0;
+    fn factorial_fuel_to_position(&self, target_position: i64) -> Result<u128, ShipLineError> {
+        self.positions.iter()
+            .try_fold(0u128, |total, (position, count)| {
+                factorial_sum(target_position.abs_diff(*position)).checked_mul(*count as u128).and_then(|fuel| total.checked_add(fuel))
+            })
+            .ok_or(ShipLineError::FuelOverflow { target: target_position })
+    }
 
-        for target in 0..self.width {
-            let fuel_for_target  = self.factorial_fuel_to_position(target);
+    /// The total fuel is a sum of squared and absolute distances halved, whose continuous minimum
+    /// lies within half a position of the mean. Only the positions around the mean need
+    /// checking, the first of them wins any tie.
+    fn minimum_factorial_fuel_usage(&self) -> Result<(i64, u128), ShipLineError> {
+        let sum: i128 = self.positions.iter().map(|(position, count)| *position as i128 * *count as i128).sum();
+        let mean = sum.div_euclid(self.crab_count() as i128) as i64;
 
-            if let Some(current_val) = min_fuel {
-                if fuel_for_target < current_val {
-                    target_position = target;
-                    min_fuel = Some(fuel_for_target);
-                }
-            } else {
-                target_position = target;
-                min_fuel = Some(fuel_for_target);
-            };
-        }
+        let (low, high) = self.span();
+        let candidates = mean.saturating_sub(2).max(low)..=mean.saturating_add(2).min(high);
 
-        (target_position, min_fuel.unwrap())
+        cheapest_target(candidates, |target| self.factorial_fuel_to_position(target))
     }
 
-    fn minimum_linear_fuel_usage(&self) -> (usize, usize) {
-        let mut min_fuel: Option<usize> = None;
-        let mut target_position: usize = 0;
+    /// Any position between the two middle crabs uses the least fuel when each step costs the
+    /// same, the lower median is the first of them.
+    fn minimum_linear_fuel_usage(&self) -> (i64, u128) {
+        let middle = (self.crab_count() - 1) / 2;
 
-        for target in 0..self.width {
-            let fuel_for_target  = self.linear_fuel_to_position(target);
+        let mut seen = 0;
+        let (target, _) = *self.positions.iter()
+            .find(|(_, count)| {
+                seen += count;
+                seen > middle
+            })
+            .unwrap();
 
-            if let Some(current_val) = min_fuel {
-                if fuel_for_target < current_val {
-                    target_position = target;
-                    min_fuel = Some(fuel_for_target);
-                }
-            } else {
-                target_position = target;
-                min_fuel = Some(fuel_for_target);
-            };
+        (target, self.linear_fuel_to_position(target))
+    }
+
+    fn linear_fuel_to_position(&self, target_position: i64) -> u128 {
+        self.positions.iter()
+            .map(|(position, count)| target_position.abs_diff(*position) as u128 * *count as u128)
+            .sum()
+    }
+}
+
+impl ShipLine {
+    /// The combined weight of the crabs at each occupied position under the cost model.
+    fn weights(&self, cost: &impl FuelCost) -> Vec<u128> {
+        let mut weights = vec![0; self.positions.len()];
+        for (crab, position) in self.crabs.iter().enumerate() {
+            let idx = self.positions.binary_search_by_key(position, |(p, _)| *p).unwrap();
+            weights[idx] += cost.weight(crab);
         }
 
-        (target_position, min_fuel.unwrap())
+        weights
+    }
+
+    fn fuel_to_position(&self, target_position: i64, cost: &impl FuelCost) -> Result<u128, ShipLineError> {
+        weighted_fuel(&self.positions, &self.weights(cost), target_position, cost)
     }
 
-    fn linear_fuel_to_position(&self, target_position: usize) -> usize {
-        if target_position >= self.width {
-            panic!("attempt to move ships into position beyond the end");
+    /// Finds the first target between the outermost crabs using the least fuel under any cost
+    /// model. Convex costs give a total that falls until the best target and never falls again,
+    /// so a binary search for the first target whose neighbour isn't cheaper is enough. Costs
+    /// with breakpoints only need the targets those lead to checked, and costs made of convex
+    /// pieces are searched piece by piece. Anything else checks every target, which is refused
+    /// unless the crabs are close together. So is a search reaching a target whose total fuel
+    /// doesn't fit.
+    fn minimum_fuel_usage(&self, cost: &impl FuelCost) -> Result<(i64, u128), ShipLineError> {
+        let weights = self.weights(cost);
+        let fuel = |target: i64| weighted_fuel(&self.positions, &weights, target, cost);
+
+        let (low, high) = self.span();
+        let candidates = |offsets: &[u64]| {
+            let mut candidates: Vec<i64> = self.positions.iter()
+                .flat_map(|(position, _)| {
+                    offsets.iter().flat_map(move |offset| {
+                        [position.checked_sub_unsigned(*offset), position.checked_add_unsigned(*offset)]
+                    })
+                })
+                .flatten()
+                .chain([low, high])
+                .filter(|target| (low..=high).contains(target))
+                .collect();
+
+            candidates.sort_unstable();
+            candidates.dedup();
+            candidates
+        };
+
+        if cost::is_convex(cost, high.abs_diff(low)) {
+            let target = first_convex_minimum(fuel, low, high)?;
+            Ok((target, fuel(target)?))
+        } else if let Some(breakpoints) = cost.breakpoints() {
+            cheapest_target(candidates(&breakpoints), fuel)
+        } else if let Some(pieces) = cost.convex_pieces() {
+            let minima = candidates(&pieces).windows(2)
+                .map(|piece| first_convex_minimum(fuel, piece[0], piece[1]))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            cheapest_target(minima.into_iter().chain([low]), fuel)
+        } else if high.abs_diff(low) <= SCANNED_SPAN_LIMIT {
+            cheapest_target(low..=high, fuel)
+        } else {
+            Err(ShipLineError::TooWideToScan { span: high.abs_diff(low) })
         }
+    }
+}
 
-        let mut total_fuel = 0;
+/// The first target between `first` and `last` using the least fuel, when the fuel is convex
+/// over them.
+fn first_convex_minimum(fuel: impl Fn(i64) -> Result<u128, ShipLineError>, mut first: i64, mut last: i64) -> Result<i64, ShipLineError> {
+    while first < last {
+        let middle = (first as i128 + (last as i128 - first as i128) / 2) as i64;
 
-        for (existing_position, count) in self.positions.iter().enumerate() {
-            let distance = (target_position as isize - existing_position as isize).abs() as usize;
-            total_fuel += distance * count;
+        match fuel(middle + 1)? < fuel(middle)? {
+            true => first = middle + 1,
+            false => last = middle,
         }
+    }
 
-        total_fuel
+    Ok(first)
+}
+
+/// The target using the least fuel among at least one, the first of them on a tie.
+fn cheapest_target(targets: impl IntoIterator<Item = i64>, fuel: impl Fn(i64) -> Result<u128, ShipLineError>) -> Result<(i64, u128), ShipLineError> {
+    let mut cheapest: Option<(u128, i64)> = None;
+    for target in targets {
+        let candidate = (fuel(target)?, target);
+        if cheapest.is_none_or(|cheapest| candidate < cheapest) {
+            cheapest = Some(candidate);
+        }
     }
+
+    let (fuel, target) = cheapest.unwrap();
+    Ok((target, fuel))
 }
 
-impl From<Vec<usize>> for ShipLine {
-    fn from(pos_list: Vec<usize>) -> Self {
-        let width: usize = *pos_list.iter().max().unwrap() + 1;
-        let mut positions: Vec<usize> = vec![0; width];
+fn weighted_fuel(positions: &[(i64, u64)], weights: &[u128], target_position: i64, cost: &impl FuelCost) -> Result<u128, ShipLineError> {
+    positions.iter()
+        .zip(weights.iter())
+        .try_fold(0u128, |total, ((position, _), weight)| {
+            weight.checked_mul(cost.cost(target_position.abs_diff(*position))).and_then(|fuel| total.checked_add(fuel))
+        })
+        .ok_or(ShipLineError::FuelOverflow { target: target_position })
+}
+
+impl TryFrom<Vec<i64>> for ShipLine {
+    type Error = ShipLineError;
 
-        for pos in pos_list.into_iter() {
-            positions[pos] += 1;
+    fn try_from(crabs: Vec<i64>) -> Result<Self, ShipLineError> {
+        if crabs.is_empty() {
+            return Err(ShipLineError::Empty);
         }
 
-        Self { positions, width }
+        let mut sorted = crabs.clone();
+        sorted.sort_unstable();
+
+        let mut positions: Vec<(i64, u64)> = vec![];
+        for position in sorted {
+            match positions.last_mut() {
+                Some((last, count)) if *last == position => *count += 1,
+                _ => positions.push((position, 1)),
+            }
+        }
+
+        Ok(Self { crabs, positions })
     }
 }
 
+/// Reads the comma separated crab positions, fields being numbered from zero.
+fn parse_positions(line: &str) -> Result<Vec<i64>, ShipLineError> {
+    if line.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    line.trim()
+        .split(',')
+        .map(|v| v.trim())
+        .enumerate()
+        .map(|(field, value)| value.parse().map_err(|_| ShipLineError::InvalidPosition { field, value: value.to_string() }))
+        .collect()
+}
+
 fn main() {
     let input_entries = read_puzzle_input(7);
-    let positions: Vec<usize> = input_entries[0].split(",").map(|i| i.parse::<usize>().unwrap()).collect();
 
-    let ship_line = ShipLine::from(positions);
+    let ship_line = match parse_positions(input_entries.first().map_or("", String::as_str)).and_then(ShipLine::try_from) {
+        Ok(ship_line) => ship_line,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
     println!("minimum fuel for linear alignment: {:?}", ship_line.minimum_linear_fuel_usage());
-    println!("minimum fuel for factorial alignment: {:?}", ship_line.minimum_factorial_fuel_usage());
+    match ship_line.minimum_factorial_fuel_usage() {
+        Ok(result) => println!("minimum fuel for factorial alignment: {:?}", result),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+
+    // Other cost models can be tried with `--cost` followed by a model `parse_cost_model`
+    // understands. `--target` followed by a position also prints the fuel each of them needs to
+    // move every crab there. `--profile` followed by `csv`, `ascii` or `svg` prints the fuel for
+    // every target under the last cost model given, or the triangular cost when there's none.
+    let mut models: Vec<(String, Box<dyn FuelCost>)> = Vec::new();
+    let mut target = None;
+    let mut profile = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cost" => {
+                let model = args.next().unwrap_or_default();
+                match parse_cost_model(&model) {
+                    Ok(cost) => models.push((model, cost)),
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--target" => {
+                let position = args.next().unwrap_or_default();
+                match position.parse::<i64>() {
+                    Ok(position) => target = Some(position),
+                    Err(_) => {
+                        eprintln!("'{}' is not a valid target", position);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--profile" => profile = Some(args.next().unwrap_or_default()),
+            _ => {}
+        }
+    }
+
+    if let Some(format) = &profile {
+        if !["csv", "ascii", "svg"].contains(&format.as_str()) {
+            eprintln!("unknown profile format '{}'", format);
+            std::process::exit(1);
+        }
+    }
+
+    for (model, cost) in models.iter() {
+        report(&ship_line, model, cost);
+        if let Some(target) = target {
+            match ship_line.fuel_to_position(target, cost) {
+                Ok(fuel) => println!("fuel for {} alignment at {}: {}", model, target, fuel),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    if let Some(format) = profile {
+        match models.last() {
+            Some((_, cost)) => print_profile(&ship_line, cost, &format),
+            None => print_profile(&ship_line, &cost::Triangular, &format),
+        }
+    }
+}
+
+fn report(ship_line: &ShipLine, model: &str, cost: &impl FuelCost) {
+    match ship_line.minimum_fuel_usage(cost) {
+        Ok(result) => println!("minimum fuel for {} alignment: {:?}", model, result),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_profile(ship_line: &ShipLine, cost: &impl FuelCost, format: &str) {
+    let targets = match ship_line.profile_window(cost, PROFILED_TARGET_LIMIT) {
+        Ok(targets) => targets,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let (low, high) = ship_line.span();
+    if targets != (low..=high) {
+        eprintln!("profiling targets {} to {} around the minimum", targets.start(), targets.end());
+    }
+
+    let profile = match ship_line.fuel_profile(cost, targets) {
+        Ok(profile) => profile,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    match format {
+        "csv" => print!("{}", profile.to_csv()),
+        "ascii" => print!("{}", profile.render_ascii(100, 20)),
+        _ => print!("{}", profile.to_svg(800, 400)),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use common::Rng;
+
     const REFERENCE_INPUT: &str = "16,1,2,0,4,2,7,1,2,14";
 
     #[test]
     fn test_first_part() {
         let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
-        let positions: Vec<usize> = input[0].split(',').map(|i| i.parse::<usize>().unwrap()).collect();
+        let positions = parse_positions(&input[0]).unwrap();
 
         assert_eq!(positions.len(), 10);
 
-        let ship_line = ShipLine::from(positions);
+        let ship_line = ShipLine::try_from(positions).unwrap();
 
         assert_eq!(ship_line.linear_fuel_to_position(1), 41);
         assert_eq!(ship_line.linear_fuel_to_position(2), 37);
@@ -145,13 +450,160 @@ mod tests {
     #[test]
     fn test_second_part() {
         let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
-        let positions: Vec<usize> = input[0].split(',').map(|i| i.parse::<usize>().unwrap()).collect();
+        let positions = parse_positions(&input[0]).unwrap();
+
+        let ship_line = ShipLine::try_from(positions).unwrap();
+
+        assert_eq!(ship_line.factorial_fuel_to_position(2).unwrap(), 206);
+        assert_eq!(ship_line.factorial_fuel_to_position(5).unwrap(), 168);
+
+        assert_eq!(ship_line.minimum_factorial_fuel_usage().unwrap(), (5, 168));
+    }
+
+    /// Tries every target, keeping the first with the least fuel.
+    fn brute_force_minimum(ship_line: &ShipLine, fuel: impl Fn(i64) -> u128) -> (i64, u128) {
+        let (low, high) = ship_line.span();
+        (low..=high).map(|target| (target, fuel(target))).min_by_key(|(target, fuel)| (*fuel, *target)).unwrap()
+    }
+
+    #[test]
+    fn test_cost_models() {
+        let input: Vec<String> = REFERENCE_INPUT.lines().map(|e| e.to_string()).collect();
+        let positions = parse_positions(&input[0]).unwrap();
+
+        let ship_line = ShipLine::try_from(positions).unwrap();
+
+        assert_eq!(ship_line.minimum_fuel_usage(&cost::Linear).unwrap(), (2, 37));
+        assert_eq!(ship_line.minimum_fuel_usage(&cost::Triangular).unwrap(), (5, 168));
+        assert_eq!(ship_line.fuel_to_position(2, &cost::Triangular).unwrap(), 206);
+        assert_eq!(ship_line.minimum_fuel_usage(&cost::Custom(|d| d as u128 * 2)).unwrap(), (2, 74));
 
-        let ship_line = ShipLine::from(positions);
+        // Moving the crab at 16 costs nothing, so the rest gather around their own median
+        let weighted = cost::Weighted { cost: cost::Linear, weights: vec![0] };
+        assert_eq!(ship_line.minimum_fuel_usage(&weighted).unwrap(), (2, 23));
 
-        assert_eq!(ship_line.factorial_fuel_to_position(2), 206);
-        assert_eq!(ship_line.factorial_fuel_to_position(5), 168);
+        // A low cap makes every crab equally expensive to move, so the crowded spot wins
+        let capped = cost::Capped { cost: cost::Linear, cap: 1 };
+        assert_eq!(ship_line.minimum_fuel_usage(&capped).unwrap(), (2, 7));
+    }
 
-        assert_eq!(ship_line.minimum_factorial_fuel_usage(), (5, 168));
+    #[test]
+    fn test_generic_minimizer_matches_brute_force() {
+        let mut rng = Rng::new(48);
+
+        for _ in 0..300 {
+            let crabs = 1 + rng.below(30);
+            let spread = 1 + rng.below(50);
+            let positions: Vec<i64> = (0..crabs).map(|_| rng.below(spread) as i64 - 20).collect();
+            let weights: Vec<u64> = (0..crabs).map(|_| rng.below(5) as u64).collect();
+
+            let ship_line = ShipLine::try_from(positions.clone()).unwrap();
+            let quadratic = cost::Quadratic;
+            let weighted = cost::Weighted { cost: cost::Triangular, weights };
+            let capped = cost::Capped { cost: cost::Quadratic, cap: 1 + rng.below(200) as u128 };
+            let capped_linear = cost::Capped { cost: cost::Linear, cap: rng.below(20) as u128 };
+            let capped_triangular = cost::Capped { cost: cost::Triangular, cap: rng.below(100) as u128 };
+            let custom = cost::Custom(|d: u64| d as u128 / 3);
+
+            assert_eq!(ship_line.minimum_fuel_usage(&quadratic).unwrap(), brute_force_minimum(&ship_line, |t| ship_line.fuel_to_position(t, &quadratic).unwrap()));
+            assert_eq!(ship_line.minimum_fuel_usage(&weighted).unwrap(), brute_force_minimum(&ship_line, |t| ship_line.fuel_to_position(t, &weighted).unwrap()));
+            assert_eq!(ship_line.minimum_fuel_usage(&capped).unwrap(), brute_force_minimum(&ship_line, |t| ship_line.fuel_to_position(t, &capped).unwrap()));
+            assert_eq!(ship_line.minimum_fuel_usage(&capped_linear).unwrap(), brute_force_minimum(&ship_line, |t| ship_line.fuel_to_position(t, &capped_linear).unwrap()));
+            assert_eq!(ship_line.minimum_fuel_usage(&capped_triangular).unwrap(), brute_force_minimum(&ship_line, |t| ship_line.fuel_to_position(t, &capped_triangular).unwrap()), "positions: {:?}", positions);
+            assert_eq!(ship_line.minimum_fuel_usage(&custom).unwrap(), brute_force_minimum(&ship_line, |t| ship_line.fuel_to_position(t, &custom).unwrap()), "positions: {:?}", positions);
+        }
+    }
+
+    #[test]
+    fn test_cost_model_parser() {
+        let ship_line = ShipLine::try_from(vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14]).unwrap();
+
+        let cost = parse_cost_model("triangular").unwrap();
+        assert_eq!(ship_line.minimum_fuel_usage(&cost).unwrap(), (5, 168));
+
+        let cost = parse_cost_model("capped:1").unwrap();
+        assert_eq!(cost.cost(5), 1);
+
+        let cost = parse_cost_model("power:3").unwrap();
+        assert_eq!(cost.cost(4), 64);
+        assert_eq!(cost.cost(u64::MAX), u128::MAX);
+
+        let path = std::env::temp_dir().join(format!("day_07_weights_{}.txt", std::process::id()));
+        std::fs::write(&path, "2,1,1,1,1,1,1,1,1,1\n").unwrap();
+        let cost = parse_cost_model(&format!("weighted:{}", path.display())).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(ship_line.fuel_to_position(5, &cost).unwrap(), 168 + 66);
+
+        std::fs::write(&path, "2,x").unwrap();
+        let err = parse_cost_model(&format!("weighted:{}", path.display())).err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(err, Some(CostModelError::InvalidWeight { field: 1, value: "x".to_string() }));
+        assert!(matches!(parse_cost_model("weighted:/no/such/file"), Err(CostModelError::UnreadableWeights { .. })));
+
+        assert_eq!(parse_cost_model("power:-1").err(), Some(CostModelError::InvalidExponent { value: "-1".to_string() }));
+        assert_eq!(parse_cost_model("capped:x").err(), Some(CostModelError::InvalidCap { value: "x".to_string() }));
+        assert_eq!(parse_cost_model("cubic").err(), Some(CostModelError::Unknown { model: "cubic".to_string() }));
+    }
+
+    #[test]
+    fn test_sparse_signed_positions() {
+        assert_eq!(ShipLine::try_from(vec![]).err(), Some(ShipLineError::Empty));
+        assert_eq!(parse_positions(" \n").and_then(ShipLine::try_from).err(), Some(ShipLineError::Empty));
+        assert_eq!(parse_positions("1,-x"), Err(ShipLineError::InvalidPosition { field: 1, value: "-x".to_string() }));
+
+        let ship_line = ShipLine::try_from(parse_positions("-5, 10,-3").unwrap()).unwrap();
+        assert_eq!(ship_line.positions, vec![(-5, 1), (-3, 1), (10, 1)]);
+        assert_eq!(ship_line.minimum_linear_fuel_usage(), (-3, 15));
+        assert_eq!(ship_line.minimum_factorial_fuel_usage().unwrap(), ship_line.minimum_fuel_usage(&cost::Triangular).unwrap());
+
+        // Crabs far apart only take up room for the positions they occupy
+        let far = 1_i64 << 40;
+        let ship_line = ShipLine::try_from(vec![0, far, far, -far]).unwrap();
+        assert_eq!(ship_line.minimum_linear_fuel_usage(), (0, 3 * far as u128));
+        assert_eq!(ship_line.minimum_fuel_usage(&cost::Linear).unwrap(), (0, 3 * far as u128));
+        assert_eq!(ship_line.minimum_factorial_fuel_usage().unwrap(), ship_line.minimum_fuel_usage(&cost::Triangular).unwrap());
+        assert_eq!(ship_line.minimum_fuel_usage(&cost::Quadratic).unwrap(), (far / 4, ship_line.fuel_to_position(far / 4, &cost::Quadratic).unwrap()));
+
+        // Capped steps only need the targets around each crab checked
+        let ship_line = ShipLine::try_from(vec![0, 1, 2, far]).unwrap();
+        assert_eq!(ship_line.minimum_fuel_usage(&cost::Capped { cost: cost::Linear, cap: 5 }).unwrap(), (1, 7));
+        assert_eq!(ship_line.minimum_fuel_usage(&cost::Capped { cost: cost::Triangular, cap: 100 }).unwrap(), (1, 102));
+        assert_eq!(
+            ship_line.minimum_fuel_usage(&cost::Custom(|d: u64| (d as f64).sqrt() as u128)),
+            Err(ShipLineError::TooWideToScan { span: far as u64 })
+        );
+
+        let ship_line = ShipLine::try_from(vec![i64::MIN, i64::MAX]).unwrap();
+        assert_eq!(ship_line.minimum_linear_fuel_usage(), (i64::MIN, u64::MAX as u128));
+
+        // Eight crabs at either end need more fuel than can be counted wherever they meet
+        let ship_line = ShipLine::try_from([vec![i64::MIN; 8], vec![i64::MAX; 8]].concat()).unwrap();
+        assert!(matches!(ship_line.minimum_factorial_fuel_usage(), Err(ShipLineError::FuelOverflow { .. })));
+        assert!(matches!(ship_line.minimum_fuel_usage(&cost::Triangular), Err(ShipLineError::FuelOverflow { .. })));
+        assert_eq!(ship_line.fuel_to_position(i64::MIN, &cost::Quadratic), Err(ShipLineError::FuelOverflow { target: i64::MIN }));
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut rng = Rng::new(7);
+
+        for _ in 0..500 {
+            let crabs = 1 + rng.below(40);
+            let spread = 1 + rng.below(60);
+            let positions: Vec<i64> = (0..crabs).map(|_| rng.below(spread) as i64 - 20).collect();
+
+            let ship_line = ShipLine::try_from(positions.clone()).unwrap();
+
+            assert_eq!(
+                ship_line.minimum_linear_fuel_usage(),
+                brute_force_minimum(&ship_line, |t| ship_line.linear_fuel_to_position(t)),
+                "positions: {:?}", positions
+            );
+            assert_eq!(
+                ship_line.minimum_factorial_fuel_usage().unwrap(),
+                brute_force_minimum(&ship_line, |t| ship_line.factorial_fuel_to_position(t).unwrap()),
+                "positions: {:?}", positions
+            );
+        }
     }
 }
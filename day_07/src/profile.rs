@@ -0,0 +1,355 @@
+//! The total fuel for every target in a range under a cost model. Costs that are polynomials of
+//! the distance are profiled in a single sweep, moving the target one position at a time and
+//! updating the sums of distances and squared distances from the crabs on either side, as long
+//! as those sums fit. Other costs, capped ones included, are evaluated target by target which takes time proportional to
+//! the number of targets times the number of occupied positions.
+//!
+//! Several targets can share the minimum, the profile reports all of them and both the CSV and
+//! the plots mark them.
+
+use std::fmt::Write;
+use std::ops::RangeInclusive;
+
+use crate::cost::FuelCost;
+use crate::{weighted_fuel, ShipLine, ShipLineError};
+
+#[derive(Debug, PartialEq)]
+pub struct FuelProfile {
+    pub start: i64,
+    pub fuel: Vec<u128>,
+}
+
+impl ShipLine {
+    /// The targets to profile, at most `limit` of them. The whole span between the outermost
+    /// crabs when it fits, otherwise a window centred on the first target using the least fuel
+    /// and moved inside the span where it would stick out.
+    pub fn profile_window(&self, cost: &impl FuelCost, limit: u64) -> Result<RangeInclusive<i64>, ShipLineError> {
+        let (low, high) = self.span();
+        if high.abs_diff(low) < limit {
+            return Ok(low..=high);
+        }
+
+        let (target, _) = self.minimum_fuel_usage(cost)?;
+        let reach = limit.saturating_sub(1) as i128;
+
+        let start = (target as i128 - reach / 2).clamp(low as i128, high as i128 - reach);
+        Ok(start as i64..=(start + reach) as i64)
+    }
+
+    pub fn fuel_profile(&self, cost: &impl FuelCost, targets: RangeInclusive<i64>) -> Result<FuelProfile, ShipLineError> {
+        let weights = self.weights(cost);
+        let start = *targets.start();
+
+        if targets.is_empty() {
+            return Ok(FuelProfile { start, fuel: vec![] });
+        }
+
+        let fuel = match cost.polynomial().and_then(|terms| polynomial_profile(&self.positions, &weights, targets.clone(), terms)) {
+            Some(fuel) => fuel,
+            None => targets.map(|target| weighted_fuel(&self.positions, &weights, target, cost)).collect::<Result<_, _>>()?,
+        };
+
+        Ok(FuelProfile { start, fuel })
+    }
+}
+
+/// The sweep, or nothing when the sums it keeps don't fit.
+fn polynomial_profile(positions: &[(i64, u64)], weights: &[u128], targets: RangeInclusive<i64>, (a, b, divisor): (u128, u128, u128)) -> Option<Vec<u128>> {
+    let (start, end) = (*targets.start(), *targets.end());
+    let (a, b, divisor) = (a as i128, b as i128, divisor as i128);
+
+    let total_weight = weights.iter().try_fold(0i128, |sum, weight| sum.checked_add(i128::try_from(*weight).ok()?))?;
+    let weighted_positions = positions.iter()
+        .zip(weights.iter())
+        .try_fold(0i128, |sum, ((p, _), w)| sum.checked_add((*p as i128).checked_mul(*w as i128)?))?;
+
+    let mut distances: i128 = 0;
+    let mut squares: i128 = 0;
+    for ((position, _), weight) in positions.iter().zip(weights.iter()) {
+        let distance = start.abs_diff(*position) as i128;
+        let weighted = (*weight as i128).checked_mul(distance)?;
+
+        distances = distances.checked_add(weighted)?;
+        squares = squares.checked_add(weighted.checked_mul(distance)?)?;
+    }
+
+    // The weight of the crabs at or before the current target
+    let mut next = positions.partition_point(|(position, _)| *position <= start);
+    let mut left_weight = weights[..next].iter().sum::<u128>() as i128;
+
+    let mut profile = Vec::with_capacity((end.abs_diff(start) + 1) as usize);
+    let mut target = start;
+
+    loop {
+        let fuel = a.checked_mul(squares)?.checked_add(b.checked_mul(distances)?)? / divisor;
+        profile.push(fuel as u128);
+
+        if target == end {
+            break;
+        }
+
+        // A step to the right moves every crab on the left one further away and every crab on
+        // the right one closer. Each squared distance grows by twice the signed distance plus one.
+        distances = distances.checked_add(left_weight - (total_weight - left_weight))?;
+        let growth = (target as i128).checked_mul(total_weight)?.checked_sub(weighted_positions)?;
+        squares = squares.checked_add(growth.checked_mul(2)?.checked_add(total_weight)?)?;
+        target += 1;
+
+        while next < positions.len() && positions[next].0 <= target {
+            left_weight += weights[next] as i128;
+            next += 1;
+        }
+    }
+
+    Some(profile)
+}
+
+impl FuelProfile {
+    pub fn targets(&self) -> impl Iterator<Item = (i64, u128)> + '_ {
+        self.fuel.iter().enumerate().map(|(idx, fuel)| (self.start + idx as i64, *fuel))
+    }
+
+    pub fn minimum(&self) -> Option<u128> {
+        self.fuel.iter().min().copied()
+    }
+
+    /// Every target using the least fuel, in increasing order.
+    pub fn minimizers(&self) -> Vec<i64> {
+        let minimum = self.minimum();
+        self.targets().filter(|(_, fuel)| Some(*fuel) == minimum).map(|(target, _)| target).collect()
+    }
+
+    /// The first and last targets using the least fuel. Convex costs use the least fuel at
+    /// every target in between as well.
+    pub fn tie_range(&self) -> Option<(i64, i64)> {
+        let minimizers = self.minimizers();
+        Some((*minimizers.first()?, *minimizers.last()?))
+    }
+
+    pub fn to_csv(&self) -> String {
+        let minimum = self.minimum();
+
+        let mut output = "target,fuel,minimum\n".to_string();
+        for (target, fuel) in self.targets() {
+            writeln!(output, "{},{},{}", target, fuel, (Some(fuel) == minimum) as u8).unwrap();
+        }
+
+        output
+    }
+
+    fn summary(&self) -> String {
+        match (self.minimum(), self.tie_range()) {
+            (Some(minimum), Some((first, last))) if first == last => format!("minimum fuel {} at target {}", minimum, first),
+            (Some(minimum), Some((first, last))) => format!(
+                "minimum fuel {} at {} targets from {} to {}",
+                minimum, self.minimizers().len(), first, last
+            ),
+            _ => "no targets".to_string(),
+        }
+    }
+
+    /// Groups the targets into at most `width` columns, each showing the least fuel of its
+    /// targets so the minimum is never averaged away. Columns holding a minimizing target are
+    /// marked below the axis.
+    pub fn render_ascii(&self, width: usize, height: usize) -> String {
+        let (low, high) = match (self.fuel.iter().min(), self.fuel.iter().max()) {
+            (Some(low), Some(high)) => (*low, *high),
+            _ => return format!("{}\n", self.summary()),
+        };
+
+        let columns = width.min(self.fuel.len()).max(1);
+        let groups: Vec<&[u128]> = (0..columns)
+            .map(|column| &self.fuel[(column * self.fuel.len() / columns)..((column + 1) * self.fuel.len() / columns)])
+            .collect();
+
+        let rows: Vec<usize> = groups.iter()
+            .map(|group| {
+                let fuel = *group.iter().min().unwrap();
+                match high - low {
+                    0 => 0,
+                    span => ((fuel - low) as f64 / span as f64 * (height - 1) as f64).round() as usize,
+                }
+            })
+            .collect();
+
+        let margin = high.to_string().len();
+        let mut output = String::new();
+
+        for row in (0..height).rev() {
+            let label = match row {
+                _ if row == height - 1 => high.to_string(),
+                0 => low.to_string(),
+                _ => String::new(),
+            };
+
+            let line: String = rows.iter().map(|r| if *r == row { '*' } else { ' ' }).collect();
+            writeln!(output, "{:>margin$} |{}", label, line.trim_end(), margin = margin).unwrap();
+        }
+
+        let minimum = self.minimum();
+        let markers: String = groups.iter().map(|group| if group.iter().any(|f| Some(*f) == minimum) { '^' } else { ' ' }).collect();
+
+        writeln!(output, "{} +{}", " ".repeat(margin), "-".repeat(columns)).unwrap();
+        writeln!(output, "{}  {}", " ".repeat(margin), markers.trim_end()).unwrap();
+        writeln!(output, "{}", self.summary()).unwrap();
+
+        output
+    }
+
+    /// Plots the profile as a line, shading the tie range and circling every minimizing target.
+    pub fn to_svg(&self, width: usize, height: usize) -> String {
+        let mut output = String::new();
+        writeln!(output, "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {} {}\">", width, height).unwrap();
+
+        if let (Some(low), Some(high)) = (self.fuel.iter().min(), self.fuel.iter().max()) {
+            let x = |idx: usize| match self.fuel.len() {
+                1 => width as f64 / 2.0,
+                len => idx as f64 / (len - 1) as f64 * width as f64,
+            };
+            let y = |fuel: u128| match high - low {
+                0 => height as f64 / 2.0,
+                span => height as f64 - (fuel - low) as f64 / span as f64 * height as f64,
+            };
+
+            if let Some((first, last)) = self.tie_range() {
+                let (left, right) = (x((first - self.start) as usize), x((last - self.start) as usize));
+                writeln!(
+                    output,
+                    "  <rect x=\"{:.2}\" y=\"0\" width=\"{:.2}\" height=\"{}\" fill=\"#ffe08a\" opacity=\"0.5\"/>",
+                    left, right - left, height
+                ).unwrap();
+            }
+
+            let points: Vec<String> = self.fuel.iter().enumerate().map(|(idx, fuel)| format!("{:.2},{:.2}", x(idx), y(*fuel))).collect();
+            writeln!(output, "  <polyline points=\"{}\" fill=\"none\" stroke=\"#1f77b4\"/>", points.join(" ")).unwrap();
+
+            for target in self.minimizers() {
+                let idx = (target - self.start) as usize;
+                writeln!(
+                    output,
+                    "  <circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"3\" fill=\"#d62728\"><title>{}: {}</title></circle>",
+                    x(idx), y(self.fuel[idx]), target, self.fuel[idx]
+                ).unwrap();
+            }
+        }
+
+        writeln!(output, "  <text x=\"4\" y=\"14\" font-size=\"12\">{}</text>", self.summary()).unwrap();
+        writeln!(output, "</svg>").unwrap();
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use common::Rng;
+
+    use crate::cost;
+
+    fn reference_line() -> ShipLine {
+        ShipLine::try_from(vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14]).unwrap()
+    }
+
+    #[test]
+    fn test_reference_profile() {
+        let ship_line = reference_line();
+
+        let profile = ship_line.fuel_profile(&cost::Linear, 0..=16).unwrap();
+        assert_eq!(profile.fuel.len(), 17);
+        assert_eq!(profile.fuel[1..4], [41, 37, 39]);
+        assert_eq!(profile.fuel[10], 71);
+        assert_eq!(profile.tie_range(), Some((2, 2)));
+
+        let profile = ship_line.fuel_profile(&cost::Triangular, 0..=16).unwrap();
+        assert_eq!((profile.fuel[2], profile.fuel[5]), (206, 168));
+        assert_eq!(profile.minimizers(), vec![5]);
+    }
+
+    #[test]
+    fn test_sweep_matches_direct_evaluation() {
+        let mut rng = Rng::new(50);
+
+        for _ in 0..200 {
+            let crabs = 1 + rng.below(20);
+            let positions: Vec<i64> = (0..crabs).map(|_| rng.below(40) as i64 - 20).collect();
+            let weights: Vec<u64> = (0..crabs).map(|_| rng.below(4) as u64).collect();
+
+            let ship_line = ShipLine::try_from(positions).unwrap();
+            let targets = -25..=25;
+
+            let profile = ship_line.fuel_profile(&cost::Weighted { cost: cost::Triangular, weights: weights.clone() }, targets.clone()).unwrap();
+            let direct = ship_line.fuel_profile(&cost::Weighted { cost: cost::Custom(|d| cost::Triangular.cost(d)), weights }, targets.clone()).unwrap();
+            assert_eq!(profile, direct);
+
+            let profile = ship_line.fuel_profile(&cost::Quadratic, targets.clone()).unwrap();
+            let expected: Vec<u128> = targets.map(|t| ship_line.fuel_to_position(t, &cost::Quadratic).unwrap()).collect();
+            assert_eq!(profile.fuel, expected);
+        }
+    }
+
+    #[test]
+    fn test_tie_range() {
+        // Anywhere between two crabs costs the same when each step costs the same
+        let ship_line = ShipLine::try_from(vec![-3, 4]).unwrap();
+        let profile = ship_line.fuel_profile(&cost::Linear, -5..=6).unwrap();
+
+        assert_eq!(profile.tie_range(), Some((-3, 4)));
+        assert_eq!(profile.minimizers().len(), 8);
+        assert!(profile.to_csv().starts_with("target,fuel,minimum\n-5,11,0\n-4,9,0\n-3,7,1\n"));
+
+        let (start, end) = (3, 2);
+        assert_eq!(ship_line.fuel_profile(&cost::Linear, start..=end).unwrap().tie_range(), None);
+    }
+
+    #[test]
+    fn test_profile_window() {
+        assert_eq!(reference_line().profile_window(&cost::Triangular, 100), Ok(0..=16));
+        assert_eq!(reference_line().profile_window(&cost::Triangular, 5), Ok(3..=7));
+        assert_eq!(reference_line().profile_window(&cost::Triangular, 17), Ok(0..=16));
+
+        // Windows near the outermost crabs stay inside the span
+        let far = 1_i64 << 40;
+        let ship_line = ShipLine::try_from(vec![-far, -far, -far, far]).unwrap();
+        assert_eq!(ship_line.profile_window(&cost::Linear, 1000), Ok(-far..=-far + 999));
+        assert_eq!(ship_line.fuel_profile(&cost::Linear, -far..=-far + 999).unwrap().tie_range(), Some((-far, -far)));
+
+        // Sums too large for the sweep fall back to evaluating each target
+        let ship_line = ShipLine::try_from(vec![i64::MIN, i64::MAX]).unwrap();
+        let targets = ship_line.profile_window(&cost::Triangular, 1000).unwrap();
+        let expected: Vec<u128> = targets.clone().map(|t| ship_line.fuel_to_position(t, &cost::Triangular).unwrap()).collect();
+        assert_eq!(ship_line.fuel_profile(&cost::Triangular, targets).unwrap().fuel, expected);
+
+        let ship_line = ShipLine::try_from([vec![i64::MIN; 8], vec![i64::MAX; 8]].concat()).unwrap();
+        assert_eq!(ship_line.fuel_profile(&cost::Triangular, -1..=0), Err(ShipLineError::FuelOverflow { target: -1 }));
+    }
+
+    #[test]
+    fn test_plots() {
+        let profile = reference_line().fuel_profile(&cost::Triangular, 0..=16).unwrap();
+
+        let ascii = profile.render_ascii(80, 5);
+        let lines: Vec<&str> = ascii.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert!(lines[0].starts_with("817 |"));
+        assert_eq!(lines[4], "168 | ********");
+        assert_eq!(lines[6], "          ^");
+        assert_eq!(lines[7], "minimum fuel 168 at target 5");
+
+        // Grouped columns keep the least fuel of their targets, so the minimum still shows
+        let ascii = profile.render_ascii(4, 5);
+        let lines: Vec<&str> = ascii.lines().collect();
+        assert_eq!(lines[4], "168 |***");
+        assert_eq!(lines[6], "      ^");
+
+        let svg = reference_line().fuel_profile(&cost::Linear, 0..=16).unwrap().to_svg(170, 100);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 170 100\">"));
+        assert_eq!(svg.matches("<circle").count(), 1);
+        assert!(svg.contains("<title>2: 37</title>"));
+        assert!(svg.contains("minimum fuel 37 at target 2"));
+
+        let svg = ShipLine::try_from(vec![-3, 4]).unwrap().fuel_profile(&cost::Linear, -5..=6).unwrap().to_svg(110, 100);
+        assert_eq!(svg.matches("<circle").count(), 8);
+        assert!(svg.contains("minimum fuel 7 at 8 targets from -3 to 4"));
+    }
+}